@@ -0,0 +1,105 @@
+//! Compare the bounding volume hierarchy with a linear search over the shapes.
+//!
+//! Run with `cargo bench --bench bvh`.
+use rand::prelude::*;
+use ray_tracer::camera::{Camera, Fov, Projection};
+use ray_tracer::materials::Material;
+use ray_tracer::scene::Scene;
+use ray_tracer::settings::RenderSettings;
+use ray_tracer::shapes::{Shape, Sphere};
+use ray_tracer::utils::color::Color;
+use ray_tracer::utils::ray::Ray;
+use ray_tracer::utils::vector::Vector;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const RAYS_COUNT: usize = 10_000;
+
+fn random_vector(rng: &mut StdRng, scale: f32) -> Vector {
+    Vector {
+        x: rng.random_range(-scale..scale),
+        y: rng.random_range(-scale..scale),
+        z: rng.random_range(-scale..scale),
+    }
+}
+
+fn make_scene(shapes_count: usize, rng: &mut StdRng) -> Scene {
+    let material = Material::diffuse(Color::WHITE);
+    let shapes: Vec<Box<dyn Shape>> = (0..shapes_count)
+        .map(|_| {
+            Box::new(Sphere {
+                origin: random_vector(rng, 100.),
+                radius: rng.random_range(0.1..1.),
+                material: material.clone(),
+            }) as Box<dyn Shape>
+        })
+        .collect();
+    let camera = Camera {
+        point: Vector {
+            x: 0.,
+            y: 0.,
+            z: 0.,
+        },
+        projection: Projection::Perspective {
+            fov: Fov::Horizontal(1.),
+        },
+        direction: Vector {
+            x: 0.,
+            y: 0.,
+            z: 1.,
+        },
+        up: Vector {
+            x: 0.,
+            y: 1.,
+            z: 0.,
+        },
+        right: Vector {
+            x: 1.,
+            y: 0.,
+            z: 0.,
+        },
+        lens: None,
+    };
+    Scene::new(shapes, Vec::new(), camera, RenderSettings::default())
+}
+
+fn time<F: FnMut(&Ray) -> Option<f32>>(rays: &[Ray], mut f: F) -> Duration {
+    let start = Instant::now();
+    for ray in rays {
+        black_box(f(ray));
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let rays: Vec<Ray> = (0..RAYS_COUNT)
+        .map(|_| {
+            Ray::new(
+                random_vector(&mut rng, 100.),
+                random_vector(&mut rng, 1.).normalize(),
+            )
+        })
+        .collect();
+
+    for shapes_count in [10, 100, 1_000, 10_000] {
+        let scene = make_scene(shapes_count, &mut rng);
+        let linear = time(&rays, |ray| {
+            scene
+                .shapes()
+                .iter()
+                .filter_map(|shape| shape.get_intersection(ray))
+                .map(|intersection| intersection.d)
+                .min_by(f32::total_cmp)
+        });
+        let bvh = time(&rays, |ray| {
+            scene.intersect(ray).map(|intersection| intersection.d)
+        });
+        println!(
+            "{shapes_count:>6} spheres: linear {:>10.3?}, bvh {:>10.3?}, speedup x{:.1}",
+            linear / RAYS_COUNT as u32,
+            bvh / RAYS_COUNT as u32,
+            linear.as_secs_f64() / bvh.as_secs_f64()
+        );
+    }
+}
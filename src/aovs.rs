@@ -0,0 +1,373 @@
+//! Arbitrary output variables: auxiliary images rendered along with the beauty image, for
+//! compositing and denoising.
+//!
+//! The passes are captured on the surface hit by each camera ray. The depth and the
+//! identifiers are taken from the first sample of a pixel, as averaging them would blend
+//! the values of different surfaces, while the other passes are averaged over the samples
+//! like the beauty image. The pixels where no shape is hit hold an infinite depth and 0 in
+//! the other passes.
+use crate::framebuffer::{Framebuffer, ToneMapping};
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use crate::utils::color::Color;
+use crate::utils::intersection::Intersection;
+use image::{Rgb, RgbImage};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The passes which can be rendered along with the beauty image
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aov {
+    /// The distance from the camera to the surface, along the ray
+    Depth,
+    /// The point of the surface, in world coordinates
+    Position,
+    /// The normal of the surface, perturbed by the normal maps
+    Normal,
+    /// The color of the material, without lighting
+    Albedo,
+    /// The index of the shape, from 1
+    ObjectId,
+    /// The identifier of the material, from 1, see [`Scene::material_id`]
+    MaterialId,
+    /// The light received straight from the lights or emitted by the surface, see
+    /// [`crate::integrators::Integrator::split_radiance`]
+    Direct,
+    /// The light which bounced on other surfaces before reaching the surface
+    Indirect,
+    /// The fraction of the light of the lights, weighted by their intensity, which is
+    /// blocked by other shapes: 1 where the surface is fully in shadow
+    Shadow,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 9] = [
+        Aov::Depth,
+        Aov::Position,
+        Aov::Normal,
+        Aov::Albedo,
+        Aov::ObjectId,
+        Aov::MaterialId,
+        Aov::Direct,
+        Aov::Indirect,
+        Aov::Shadow,
+    ];
+
+    /// The name of the pass, in scene files, on the command line and in the names of the
+    /// images
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+            Aov::Shadow => "shadow",
+        }
+    }
+
+    /// The names of the channels of the pass in OpenEXR images. The single-channel passes
+    /// hold their value in every channel of the framebuffer.
+    pub fn channels(&self) -> &'static [&'static str] {
+        match self {
+            Aov::Depth => &["Z"],
+            Aov::ObjectId | Aov::MaterialId => &["id"],
+            Aov::Shadow => &["Y"],
+            _ => &["R", "G", "B"],
+        }
+    }
+
+    /// Whether the pass holds integer identifiers
+    pub fn is_id(&self) -> bool {
+        matches!(self, Aov::ObjectId | Aov::MaterialId)
+    }
+
+    /// Whether the pass is averaged over the samples of a pixel, instead of being taken
+    /// from the first one
+    pub fn is_averaged(&self) -> bool {
+        !matches!(self, Aov::Depth | Aov::ObjectId | Aov::MaterialId)
+    }
+
+    /// Return the value of the pass for a camera ray, given the shape it hits with its index
+    /// and the direct and indirect light it carries
+    pub(crate) fn value(
+        &self,
+        scene: &Scene,
+        hit: Option<&(usize, Intersection)>,
+        (direct, indirect): (Color, Color),
+        rng: &mut dyn RngCore,
+    ) -> Color {
+        match (self, hit) {
+            (Aov::Direct, _) => direct,
+            (Aov::Indirect, _) => indirect,
+            (Aov::Depth, None) => Color::gray(f32::INFINITY),
+            (_, None) => Color::BLACK,
+            (Aov::Depth, Some((_, intersection))) => Color::gray(intersection.d),
+            (Aov::Position, Some((_, intersection))) => {
+                let point = intersection.intersection;
+                Color::from([point.x, point.y, point.z])
+            }
+            (Aov::Normal, Some((_, intersection))) => {
+                let normal = intersection.normal;
+                Color::from([normal.x, normal.y, normal.z])
+            }
+            (Aov::Albedo, Some((_, intersection))) => {
+                let material = intersection.shape.get_material();
+                material.shading(intersection.uv).albedo
+            }
+            (Aov::ObjectId, Some((index, _))) => Color::gray((index + 1) as f32),
+            (Aov::MaterialId, Some((index, _))) => Color::gray(scene.material_id(*index) as f32),
+            (Aov::Shadow, Some((_, intersection))) => Color::gray(shadow(scene, intersection, rng)),
+        }
+    }
+
+    /// Convert a pass into an 8-bit image. The lighting passes are displayed like the beauty
+    /// image and the albedo as a color, while the other passes are mapped to the range of the
+    /// image and stored without sRGB encoding: the normals from [-1, 1], the depths and the
+    /// positions from their range in the image, and the identifiers to arbitrary colors. The
+    /// `coverage` of the render, see [`Render`], leaves the background out of the positions.
+    pub fn to_rgb8(
+        &self,
+        framebuffer: &Framebuffer,
+        coverage: &Framebuffer,
+        settings: &RenderSettings,
+    ) -> RgbImage {
+        match self {
+            Aov::Direct | Aov::Indirect => {
+                framebuffer.to_rgb8(settings.exposure, settings.tone_mapping)
+            }
+            Aov::Albedo => framebuffer.to_rgb8(0., ToneMapping::Clamp),
+            Aov::Shadow => quantize(framebuffer),
+            Aov::Normal => quantize(&framebuffer.map(|normal| (normal + Color::WHITE) * 0.5)),
+            Aov::Depth => {
+                let far = framebuffer
+                    .pixels()
+                    .iter()
+                    .map(|depth| depth.r)
+                    .filter(|depth| depth.is_finite())
+                    .fold(0., f32::max);
+                // the background is as far as the farthest surface
+                quantize(&framebuffer.map(|depth| {
+                    depth.map(|depth| if depth.is_finite() { depth / far } else { 1. })
+                }))
+            }
+            Aov::Position => {
+                // the rays which miss average the origin with the points hit by the others,
+                // which the coverage removes
+                let (width, height) = (framebuffer.width(), framebuffer.height());
+                let point = |x, y| {
+                    let coverage = coverage.get_pixel(x, y).r;
+                    (coverage > 0.).then(|| (framebuffer.get_pixel(x, y) / coverage).to_array())
+                };
+                let (mut min, mut max) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+                for (x, y) in pixels(width, height) {
+                    for (i, value) in point(x, y).into_iter().flatten().enumerate() {
+                        min[i] = min[i].min(value);
+                        max[i] = max[i].max(value);
+                    }
+                }
+                // the background is black
+                let mut normalized = Framebuffer::new(width, height);
+                for (x, y) in pixels(width, height) {
+                    if let Some(point) = point(x, y) {
+                        let color = Color::from(std::array::from_fn(|i| {
+                            (point[i] - min[i]) / (max[i] - min[i]).max(f32::EPSILON)
+                        }));
+                        normalized.put_pixel(x, y, color);
+                    }
+                }
+                quantize(&normalized)
+            }
+            Aov::ObjectId | Aov::MaterialId => {
+                quantize(&framebuffer.map(|id| id_color(id.r as u32)))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Aov {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Aov {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|aov| aov.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(Aov::name).collect();
+                format!(
+                    "unknown pass `{name}`, expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// The images produced by a render
+#[derive(Debug, Clone, PartialEq)]
+pub struct Render {
+    /// the light seen by the camera
+    pub beauty: Framebuffer,
+    /// the passes rendered along with it, in the order they were requested
+    pub aovs: Vec<(Aov, Framebuffer)>,
+    /// the fraction of the camera rays of each pixel hitting a surface, in every channel, when
+    /// there are passes
+    pub coverage: Option<Framebuffer>,
+}
+
+/// Return the fraction of the light received by a surface which is blocked by other shapes,
+/// weighted by the intensity of the lights and the orientation of the surface
+fn shadow(scene: &Scene, intersection: &Intersection, rng: &mut dyn RngCore) -> f32 {
+    let (mut blocked, mut total) = (0., 0.);
+    for light in &scene.lights {
+        let samples_count = light.samples_count();
+        for _ in 0..samples_count {
+            let Some(sample) = light.illuminate(&intersection.intersection, rng) else {
+                continue;
+            };
+            let weight = sample.intensity.mean() * sample.direction.dot(&intersection.normal)
+                / samples_count as f32;
+            if weight <= 0. {
+                continue;
+            }
+            total += weight;
+            if scene.is_in_shadow(intersection, &sample) {
+                blocked += weight;
+            }
+        }
+    }
+    if total > 0. {
+        blocked / total
+    } else {
+        0.
+    }
+}
+
+/// Return an arbitrary color for an identifier, black for the background
+fn id_color(id: u32) -> Color {
+    if id == 0 {
+        return Color::BLACK;
+    }
+    // Knuth's multiplicative hash spreads the consecutive identifiers over the colors
+    let hash = id.wrapping_mul(0x9E37_79B1);
+    Color::from([24, 16, 8].map(|shift| ((hash >> shift) & 0xFF) as f32 / 255.))
+}
+
+/// Iterate over the coordinates of the pixels of an image, row by row
+fn pixels(width: u32, height: u32) -> impl Iterator<Item = (u32, u32)> {
+    (0..height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+}
+
+/// Convert a framebuffer whose channels are between 0 and 1 into an 8-bit image, without
+/// encoding them
+fn quantize(framebuffer: &Framebuffer) -> RgbImage {
+    RgbImage::from_fn(framebuffer.width(), framebuffer.height(), |x, y| {
+        let color = framebuffer.get_pixel(x, y);
+        Rgb(color
+            .to_array()
+            .map(|value| (value.clamp(0., 1.) * 255.).round() as u8))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_scene;
+    use crate::utils::ray::Ray;
+    use crate::utils::vector::Vector;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_aov_names() {
+        for aov in Aov::ALL {
+            assert_eq!(aov.name().parse(), Ok(aov));
+        }
+        assert!("beauty".parse::<Aov>().is_err());
+    }
+
+    #[test]
+    fn test_values() {
+        let scene = example_scene();
+        let mut rng = StdRng::seed_from_u64(0);
+        let ray = Ray::new(
+            scene.camera.point,
+            Vector {
+                x: 0.,
+                y: -1.,
+                z: 0.,
+            },
+        );
+        let hit = scene.intersect_indexed(&ray).unwrap();
+        let radiance = (Color::WHITE, Color::gray(0.5));
+        let value = |aov: Aov, rng: &mut StdRng| aov.value(&scene, Some(&hit), radiance, rng);
+        assert_eq!(value(Aov::Depth, &mut rng).r, hit.1.d);
+        assert_eq!(value(Aov::ObjectId, &mut rng).r, (hit.0 + 1) as f32);
+        assert_eq!(
+            value(Aov::MaterialId, &mut rng).r,
+            scene.material_id(hit.0) as f32
+        );
+        assert_eq!(value(Aov::Indirect, &mut rng), Color::gray(0.5));
+        let shadow = value(Aov::Shadow, &mut rng).r;
+        assert!((0. ..=1.).contains(&shadow));
+
+        assert!(Aov::Depth
+            .value(&scene, None, radiance, &mut rng)
+            .r
+            .is_infinite());
+        assert_eq!(
+            Aov::Normal.value(&scene, None, radiance, &mut rng),
+            Color::BLACK
+        );
+        assert_eq!(
+            Aov::Direct.value(&scene, None, radiance, &mut rng),
+            Color::WHITE
+        );
+    }
+
+    #[test]
+    fn test_to_rgb8() {
+        let mut framebuffer = Framebuffer::new(3, 1);
+        framebuffer.put_pixel(0, 0, Color::gray(1.));
+        framebuffer.put_pixel(1, 0, Color::gray(2.));
+        framebuffer.put_pixel(2, 0, Color::gray(f32::INFINITY));
+        let settings = RenderSettings::default();
+        let coverage = framebuffer.map(|_| Color::WHITE);
+        let depth = Aov::Depth.to_rgb8(&framebuffer, &coverage, &settings);
+        let depths: Vec<u8> = depth.pixels().map(|pixel| pixel.0[0]).collect();
+        assert_eq!(depths, [128, 255, 255]);
+
+        framebuffer.put_pixel(1, 0, Color::BLACK);
+        let ids = Aov::ObjectId.to_rgb8(&framebuffer, &coverage, &settings);
+        assert_eq!(ids.get_pixel(1, 0).0, [0; 3]);
+        assert_ne!(ids.get_pixel(0, 0).0, [0; 3]);
+        let normals = Aov::Normal.to_rgb8(&framebuffer, &coverage, &settings);
+        assert_eq!(normals.get_pixel(1, 0).0, [128; 3]);
+    }
+
+    #[test]
+    fn test_position_to_rgb8() {
+        let mut positions = Framebuffer::new(3, 1);
+        let mut coverage = Framebuffer::new(3, 1);
+        positions.put_pixel(0, 0, Color::from([2., 4., 6.]));
+        coverage.put_pixel(0, 0, Color::WHITE);
+        // half of the rays hit (4, 8, 12), the others the background
+        positions.put_pixel(1, 0, Color::from([2., 4., 6.]));
+        coverage.put_pixel(1, 0, Color::gray(0.5));
+        let image = Aov::Position.to_rgb8(&positions, &coverage, &RenderSettings::default());
+        // the background at the origin is left out of the range
+        assert_eq!(image.get_pixel(0, 0).0, [0; 3]);
+        assert_eq!(image.get_pixel(1, 0).0, [255; 3]);
+        assert_eq!(image.get_pixel(2, 0).0, [0; 3]);
+    }
+}
@@ -0,0 +1,310 @@
+use crate::utils::aabb::Aabb;
+use crate::utils::intersection::Intersection;
+use crate::utils::ray::Ray;
+
+/// Maximum number of primitives in a leaf of the hierarchy
+const MAX_LEAF_SIZE: usize = 4;
+/// Maximum number of nodes waiting to be visited during a traversal. The hierarchy is
+/// balanced, so this is enough for 2^63 primitives.
+const MAX_STACK_SIZE: usize = 64;
+
+/// A bounding volume hierarchy over a list of primitives, such as the shapes of a scene.
+///
+/// The hierarchy only stores the indices of the primitives: the caller provides their
+/// bounding boxes when building it, and a function intersecting a ray with a given
+/// primitive when querying it.
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// the indices of the bounded primitives, ordered so that each leaf covers a range
+    indices: Vec<usize>,
+    /// the indices of the primitives with infinite bounds, tested against every ray
+    unbounded: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Aabb,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    /// a range of `Bvh::indices`
+    Leaf { start: usize, end: usize },
+    /// the indices of the children in `Bvh::nodes`
+    Branch { left: usize, right: usize },
+}
+
+impl Bvh {
+    /// Build the hierarchy of primitives whose bounding boxes are given
+    pub fn build(bounds: &[Aabb]) -> Self {
+        let (mut indices, unbounded): (Vec<usize>, Vec<usize>) =
+            (0..bounds.len()).partition(|&i| bounds[i].is_finite());
+        let mut nodes = Vec::new();
+        if !indices.is_empty() {
+            build_node(&mut nodes, bounds, &mut indices, 0);
+        }
+        Self {
+            nodes,
+            indices,
+            unbounded,
+        }
+    }
+
+    /// Return the closest intersection of the ray with the primitives. `intersect` is given
+    /// the ray shortened to the closest intersection found so far.
+    pub fn closest<'a, F>(&self, ray: &Ray, mut intersect: F) -> Option<Intersection<'a>>
+    where
+        F: FnMut(usize, &Ray) -> Option<Intersection<'a>>,
+    {
+        let mut ray = *ray;
+        let mut closest: Option<Intersection<'a>> = None;
+        for &index in &self.unbounded {
+            let candidate = intersect(index, &ray);
+            keep_closest(&mut closest, &mut ray, candidate);
+        }
+        if self.nodes.is_empty() {
+            return closest;
+        }
+
+        let mut stack = Stack::new();
+        if let Some(t) = self.nodes[0].bounds.intersect(&ray) {
+            stack.push((0, t));
+        }
+        while let Some((node, t_near)) = stack.pop() {
+            if t_near > ray.t_max {
+                continue;
+            }
+            match self.nodes[node].kind {
+                NodeKind::Leaf { start, end } => {
+                    for &index in &self.indices[start..end] {
+                        let candidate = intersect(index, &ray);
+                        keep_closest(&mut closest, &mut ray, candidate);
+                    }
+                }
+                NodeKind::Branch { left, right } => {
+                    let t_left = self.nodes[left].bounds.intersect(&ray);
+                    let t_right = self.nodes[right].bounds.intersect(&ray);
+                    // visit the nearest child first, so that the farthest one is more likely pruned
+                    match (t_left, t_right) {
+                        (Some(t_left), Some(t_right)) if t_left < t_right => {
+                            stack.push((right, t_right));
+                            stack.push((left, t_left));
+                        }
+                        (Some(t_left), Some(t_right)) => {
+                            stack.push((left, t_left));
+                            stack.push((right, t_right));
+                        }
+                        (Some(t_left), None) => stack.push((left, t_left)),
+                        (None, Some(t_right)) => stack.push((right, t_right)),
+                        (None, None) => {}
+                    }
+                }
+            }
+        }
+        closest
+    }
+
+    /// Return whether `hit` returns true for any primitive whose bounds are in the interval
+    /// of the ray
+    pub fn any<F>(&self, ray: &Ray, mut hit: F) -> bool
+    where
+        F: FnMut(usize) -> bool,
+    {
+        if self.unbounded.iter().any(|&index| hit(index)) {
+            return true;
+        }
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let mut stack = Stack::new();
+        stack.push((0, 0.));
+        while let Some((node, _)) = stack.pop() {
+            let node = &self.nodes[node];
+            if node.bounds.intersect(ray).is_none() {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { start, end } => {
+                    if self.indices[start..end].iter().any(|&index| hit(index)) {
+                        return true;
+                    }
+                }
+                NodeKind::Branch { left, right } => {
+                    stack.push((left, 0.));
+                    stack.push((right, 0.));
+                }
+            }
+        }
+        false
+    }
+}
+
+/// The nodes waiting to be visited during a traversal, along with the distance at which the
+/// ray enters them. It is kept on the call stack to avoid an allocation per ray.
+struct Stack {
+    nodes: [(usize, f32); MAX_STACK_SIZE],
+    len: usize,
+}
+
+impl Stack {
+    fn new() -> Self {
+        Self {
+            nodes: [(0, 0.); MAX_STACK_SIZE],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, node: (usize, f32)) {
+        self.nodes[self.len] = node;
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<(usize, f32)> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.nodes[self.len])
+    }
+}
+
+/// Keep the nearest of the intersections, and shorten the ray to it
+fn keep_closest<'a>(
+    closest: &mut Option<Intersection<'a>>,
+    ray: &mut Ray,
+    candidate: Option<Intersection<'a>>,
+) {
+    if let Some(candidate) = candidate {
+        if candidate.d < ray.t_max {
+            ray.t_max = candidate.d;
+            *closest = Some(candidate);
+        }
+    }
+}
+
+/// Build the node of the given primitives, splitting them in two halves along the axis on
+/// which their centroids are the most spread. `offset` is the position of `indices` in
+/// `Bvh::indices`. Return the index of the node.
+fn build_node(
+    nodes: &mut Vec<Node>,
+    bounds: &[Aabb],
+    indices: &mut [usize],
+    offset: usize,
+) -> usize {
+    let node_bounds = indices
+        .iter()
+        .fold(Aabb::EMPTY, |aabb, &i| aabb.union(&bounds[i]));
+    let position = nodes.len();
+    nodes.push(Node {
+        bounds: node_bounds,
+        kind: NodeKind::Leaf {
+            start: offset,
+            end: offset + indices.len(),
+        },
+    });
+    if indices.len() <= MAX_LEAF_SIZE {
+        return position;
+    }
+
+    let centroids = Aabb::from_points(indices.iter().map(|&i| bounds[i].centroid()));
+    let extent = centroids.max - centroids.min;
+    let axis = |i: usize| {
+        let centroid = bounds[i].centroid();
+        if extent.x >= extent.y && extent.x >= extent.z {
+            centroid.x
+        } else if extent.y >= extent.z {
+            centroid.y
+        } else {
+            centroid.z
+        }
+    };
+    let middle = indices.len() / 2;
+    indices.select_nth_unstable_by(middle, |&a, &b| axis(a).total_cmp(&axis(b)));
+
+    let (left_indices, right_indices) = indices.split_at_mut(middle);
+    let left = build_node(nodes, bounds, left_indices, offset);
+    let right = build_node(nodes, bounds, right_indices, offset + middle);
+    nodes[position].kind = NodeKind::Branch { left, right };
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Material;
+    use crate::shapes::{Shape, Sphere};
+    use crate::utils::color::Color;
+    use crate::utils::vector::Vector;
+
+    fn spheres() -> Vec<Sphere> {
+        let material = Material::diffuse(Color::WHITE);
+        (0..100)
+            .map(|i| Sphere {
+                origin: Vector {
+                    x: (i % 10) as f32 * 3.,
+                    y: (i / 10) as f32 * 3.,
+                    z: 0.,
+                },
+                radius: 1.,
+                material: material.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_closest_matches_linear_search() {
+        let spheres = spheres();
+        let bounds: Vec<Aabb> = spheres.iter().map(|sphere| sphere.bounds()).collect();
+        let bvh = Bvh::build(&bounds);
+        for i in 0..100 {
+            let ray = Ray::new(
+                Vector {
+                    x: -5.,
+                    y: -5.,
+                    z: -10.,
+                },
+                Vector {
+                    x: (i % 10) as f32 * 3. + 5.,
+                    y: (i / 10) as f32 * 3. + 5.,
+                    z: 10.,
+                }
+                .normalize(),
+            );
+            let expected = spheres
+                .iter()
+                .filter_map(|sphere| sphere.get_intersection(&ray))
+                .min_by(|a, b| a.d.total_cmp(&b.d))
+                .map(|intersection| intersection.d);
+            let closest = bvh
+                .closest(&ray, |index, ray| spheres[index].get_intersection(ray))
+                .map(|intersection| intersection.d);
+            assert_eq!(closest, expected);
+        }
+    }
+
+    #[test]
+    fn test_any() {
+        let spheres = spheres();
+        let bounds: Vec<Aabb> = spheres.iter().map(|sphere| sphere.bounds()).collect();
+        let bvh = Bvh::build(&bounds);
+        let ray = Ray::new(
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: -10.,
+            },
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: 1.,
+            },
+        );
+        let short_ray = Ray { t_max: 5., ..ray };
+        assert!(bvh.any(&ray, |index| spheres[index]
+            .get_intersection(&ray)
+            .is_some()));
+        assert!(!bvh.any(&short_ray, |index| spheres[index]
+            .get_intersection(&short_ray)
+            .is_some()));
+    }
+}
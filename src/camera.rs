@@ -0,0 +1,647 @@
+use crate::utils::ray::Ray;
+use crate::utils::vector::Vector;
+use rand::prelude::*;
+use std::f32::consts::{E, PI};
+use std::fmt;
+
+/// The largest cosine between the vectors of a camera for them to be considered orthogonal
+const ORTHOGONALITY_TOLERANCE: f32 = 1e-3;
+
+/// A camera, whose orthonormal basis orients the image and whose projection maps the pixels
+/// to the rays leaving the camera
+pub struct Camera {
+    pub point: Vector,
+    pub projection: Projection,
+    /// the normalized direction the camera looks in, at the center of the image
+    pub direction: Vector,
+    /// the normalized direction of the top of the image
+    pub up: Vector,
+    /// the normalized direction of the right of the image
+    pub right: Vector,
+    /// the lens blurring the points out of focus, or `None` for a pinhole camera where
+    /// everything is in focus
+    pub lens: Option<Lens>,
+}
+
+/// The ways the pixels of the image are mapped to the rays leaving the camera
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    /// Rays through a pinhole, which keeps the straight lines straight. The opening angle
+    /// `fov` must be less than π.
+    Perspective { fov: Fov },
+    /// Parallel rays along the direction of the camera, leaving from a view `width` scene
+    /// units wide, which keeps the sizes independent of the distance, as in architectural
+    /// elevations
+    Orthographic { width: f32 },
+    /// A 360° panorama around the camera, the longitude spanning the width of the image and
+    /// the latitude its height, for environment maps whose width is twice their height
+    Equirectangular,
+    /// An equidistant fisheye, whose angle from the direction of the camera grows with the
+    /// distance from the center of the image. The opening angle `fov` is at most 2π, the
+    /// pixels beyond the opposite direction being black.
+    Fisheye { fov: Fov },
+}
+
+impl Projection {
+    /// Return the projection if its parameters are in their range
+    fn validate(self) -> Result<Self, CameraError> {
+        match self {
+            Projection::Perspective { fov } if !(fov.angle() > 0. && fov.angle() < PI) => {
+                Err(CameraError::InvalidFov(fov.angle()))
+            }
+            Projection::Fisheye { fov } if !(fov.angle() > 0. && fov.angle() <= 2. * PI) => {
+                Err(CameraError::InvalidFov(fov.angle()))
+            }
+            Projection::Orthographic { width } if !(width > 0. && width.is_finite()) => {
+                Err(CameraError::InvalidViewWidth(width))
+            }
+            _ => Ok(self),
+        }
+    }
+
+    /// Whether the rays are focused on a plane facing the camera, rather than on a sphere
+    /// around it
+    fn is_planar(&self) -> bool {
+        matches!(
+            self,
+            Projection::Perspective { .. } | Projection::Orthographic { .. }
+        )
+    }
+}
+
+/// A field of view, spanning either the width or the height of the image. It is kept as
+/// given, the other dimension following the aspect ratio of the rendered image.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Fov {
+    /// the opening angle across the width of the image, in radians
+    Horizontal(f32),
+    /// the opening angle across the height of the image, in radians
+    Vertical(f32),
+}
+
+impl Fov {
+    /// Return the opening angle, in radians
+    pub fn angle(&self) -> f32 {
+        match *self {
+            Fov::Horizontal(angle) | Fov::Vertical(angle) => angle,
+        }
+    }
+
+    /// Return the size, in pixels, of the dimension of an image of the given width and height
+    /// spanned by the field of view
+    fn span(&self, (width, height): (u32, u32)) -> u32 {
+        match self {
+            Fov::Horizontal(_) => width,
+            Fov::Vertical(_) => height,
+        }
+    }
+}
+
+impl Camera {
+    /// Create a camera from its basis, which is normalized. The vectors must be orthogonal.
+    pub fn new(
+        point: Vector,
+        direction: Vector,
+        up: Vector,
+        right: Vector,
+        projection: Projection,
+    ) -> Result<Self, CameraError> {
+        let direction = normalize(direction, "direction")?;
+        let up = normalize(up, "up")?;
+        let right = normalize(right, "right")?;
+        let orthogonal = [(direction, up), (direction, right), (up, right)]
+            .iter()
+            .all(|(a, b)| a.dot(b).abs() < ORTHOGONALITY_TOLERANCE);
+        if !orthogonal {
+            return Err(CameraError::NotOrthogonal);
+        }
+        Ok(Self {
+            point: finite(point, "point")?,
+            projection: projection.validate()?,
+            direction,
+            up,
+            right,
+            lens: None,
+        })
+    }
+
+    /// Create a perspective camera at `eye` looking at `target`, whose image is upright along
+    /// `up`, see [`Camera::look_at_with`]
+    pub fn look_at(eye: Vector, target: Vector, up: Vector, fov: Fov) -> Result<Self, CameraError> {
+        Self::look_at_with(eye, target, up, Projection::Perspective { fov })
+    }
+
+    /// Create a camera at `eye` looking at `target` with any projection, whose image is
+    /// upright along `up`.
+    ///
+    /// `up` only needs to be on the upper side of the image: it is made orthogonal to the
+    /// direction of the camera. When it is null or parallel to the direction, an arbitrary
+    /// vertical is picked. The basis is left-handed like the one of the existing scenes,
+    /// with the right of the image given by the cross product of `up` and the direction.
+    pub fn look_at_with(
+        eye: Vector,
+        target: Vector,
+        up: Vector,
+        projection: Projection,
+    ) -> Result<Self, CameraError> {
+        let target = finite(target, "target")?;
+        let direction = target - finite(eye, "point")?;
+        if direction.square_norm() == 0. {
+            return Err(CameraError::TargetAtEye);
+        }
+        let direction = direction.normalize();
+        let right = finite(up, "up")?.cross(&direction);
+        // the square of the sine of the angle between `up` and the direction
+        let right = if right.square_norm() > 1e-6 * up.square_norm() {
+            right.normalize()
+        } else {
+            direction.tangents().0
+        };
+        Ok(Self {
+            point: eye,
+            projection: projection.validate()?,
+            direction,
+            up: direction.cross(&right),
+            right,
+            lens: None,
+        })
+    }
+
+    /// Return a ray through the pixel at column `x` and row `y` of an image of the given
+    /// width and height, jittered randomly within the pixel, and leaving from a random point
+    /// of the lens. Return `None` if the projection does not cover the point of the pixel.
+    pub fn generate_ray(
+        &self,
+        x: u32,
+        y: u32,
+        (width, height): (u32, u32),
+        rng: &mut dyn RngCore,
+    ) -> Option<Ray> {
+        let a: f32 = rng.random_range(0.0..1.);
+        let b: f32 = rng.random_range(0.0..1.);
+        let r = (-2. * a.log(E)).sqrt();
+        let u = r * (2. * PI * b).cos() / 2.;
+        let v = r * (2. * PI * b).sin() / 2.;
+        // the point of the image, in pixels from its center towards the right and the bottom
+        let dx = x as f32 - (width as f32) / 2. - 0.5 + u;
+        let dy = y as f32 - height as f32 / 2. - 0.5 + v;
+        let (origin, direction) = match self.projection {
+            Projection::Perspective { fov } => {
+                let d = (fov.span((width, height)) / 2) as f32 / (fov.angle() / 2.).tan();
+                let direction = (self.right * dx - self.up * dy + self.direction * d).normalize();
+                (self.point, direction)
+            }
+            Projection::Orthographic { width: view_width } => {
+                let scale = view_width / width as f32;
+                let offset = (self.right * dx - self.up * dy) * scale;
+                (self.point + offset, self.direction)
+            }
+            Projection::Equirectangular => {
+                let longitude = 2. * PI * dx / width as f32;
+                let latitude = -PI * dy / height as f32;
+                let direction = self.direction * (latitude.cos() * longitude.cos())
+                    + self.right * (latitude.cos() * longitude.sin())
+                    + self.up * latitude.sin();
+                (self.point, direction)
+            }
+            Projection::Fisheye { fov } => {
+                let distance = dx.hypot(dy);
+                let angle = distance / (fov.span((width, height)) as f32 / 2.) * (fov.angle() / 2.);
+                if angle > PI {
+                    return None;
+                }
+                let mut direction = self.direction * angle.cos();
+                if distance > 0. {
+                    direction += (self.right * dx - self.up * dy) * (angle.sin() / distance);
+                }
+                (self.point, direction)
+            }
+        };
+        let Some(lens) = &self.lens else {
+            return Some(Ray::new(origin, direction));
+        };
+        // the rays through the lens converge where the ray of the pinhole meets the surface
+        // in focus
+        let (distance, (lens_right, lens_up)) = if self.projection.is_planar() {
+            let distance = lens.focus_distance / direction.dot(&self.direction);
+            (distance, (self.right, self.up))
+        } else {
+            // the panoramas focus on a sphere around the camera, the lens facing each ray
+            (lens.focus_distance, direction.tangents())
+        };
+        let focus = origin + direction * distance;
+        let (x, y) = lens.sample(rng);
+        let origin = origin + lens_right * x + lens_up * y;
+        Some(Ray::new(origin, (focus - origin).normalize()))
+    }
+}
+
+/// Return the normalized vector, or an error if it is null or not finite
+fn normalize(vector: Vector, name: &'static str) -> Result<Vector, CameraError> {
+    let vector = finite(vector, name)?;
+    if vector.square_norm() == 0. {
+        return Err(CameraError::InvalidVector(name));
+    }
+    Ok(vector.normalize())
+}
+
+/// Return the vector, or an error if one of its coordinates is not finite
+fn finite(vector: Vector, name: &'static str) -> Result<Vector, CameraError> {
+    if [vector.x, vector.y, vector.z].iter().all(|x| x.is_finite()) {
+        Ok(vector)
+    } else {
+        Err(CameraError::InvalidVector(name))
+    }
+}
+
+/// A thin lens, through which the rays leave the camera from random points to converge on
+/// the plane in focus
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Lens {
+    /// the radius of the lens
+    pub aperture: f32,
+    /// the distance from the camera to the plane in focus, along its axis
+    pub focus_distance: f32,
+    /// the number of blades of the diaphragm, whose polygonal opening shapes the blur of the
+    /// highlights out of focus. Fewer than 3 blades give a round opening.
+    pub blades: u32,
+    /// the rotation of the diaphragm, in radians
+    pub rotation: f32,
+}
+
+impl Lens {
+    /// Create a lens, whose aperture must be positive, zero giving a pinhole, and whose
+    /// plane in focus must be in front of the camera
+    pub fn new(
+        aperture: f32,
+        focus_distance: f32,
+        blades: u32,
+        rotation: f32,
+    ) -> Result<Self, CameraError> {
+        let valid = aperture >= 0. && aperture.is_finite();
+        if !(valid && focus_distance > 0. && focus_distance.is_finite()) {
+            return Err(CameraError::InvalidLens {
+                aperture,
+                focus_distance,
+            });
+        }
+        Ok(Self {
+            aperture,
+            focus_distance,
+            blades,
+            rotation,
+        })
+    }
+
+    /// Return a uniformly distributed point of the opening of the lens, as its coordinates
+    /// along the right and up vectors of the camera
+    pub fn sample(&self, rng: &mut dyn RngCore) -> (f32, f32) {
+        let (x, y) = if self.blades < 3 {
+            let r = rng.random::<f32>().sqrt();
+            let angle = 2. * PI * rng.random::<f32>();
+            (r * angle.cos(), r * angle.sin())
+        } else {
+            // the polygon is made of one triangle per blade, joining the center to an edge
+            let angle = 2. * PI / self.blades as f32;
+            let start = self.rotation + angle * rng.random_range(0..self.blades) as f32;
+            let end = start + angle;
+            let (mut a, mut b) = (rng.random::<f32>(), rng.random::<f32>());
+            if a + b > 1. {
+                (a, b) = (1. - a, 1. - b);
+            }
+            (
+                a * start.cos() + b * end.cos(),
+                a * start.sin() + b * end.sin(),
+            )
+        };
+        (x * self.aperture, y * self.aperture)
+    }
+}
+
+/// An error raised when building an invalid camera
+#[derive(Debug, Clone, PartialEq)]
+pub enum CameraError {
+    /// The camera looks at its own position, so it has no direction
+    TargetAtEye,
+    /// A vector of the camera, given by its name, is null or not finite
+    InvalidVector(&'static str),
+    /// The direction, up and right vectors of the camera are not orthogonal
+    NotOrthogonal,
+    /// The field of view, in radians, is not positive or too wide for the projection: it
+    /// must be less than π for a perspective, and at most 2π for a fisheye
+    InvalidFov(f32),
+    /// The width of the view of an orthographic projection is not strictly positive
+    InvalidViewWidth(f32),
+    /// The aperture of the lens is negative or not finite, or its focus distance is not
+    /// strictly positive
+    InvalidLens { aperture: f32, focus_distance: f32 },
+}
+
+impl fmt::Display for CameraError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CameraError::TargetAtEye => write!(f, "the camera looks at its own position"),
+            CameraError::InvalidVector(name) => {
+                write!(f, "the {name} of the camera is null or not finite")
+            }
+            CameraError::NotOrthogonal => write!(
+                f,
+                "the direction, up and right vectors of the camera are not orthogonal"
+            ),
+            CameraError::InvalidFov(fov) => write!(
+                f,
+                "the field of view of {}° is out of the range of the projection",
+                fov.to_degrees()
+            ),
+            CameraError::InvalidViewWidth(width) => {
+                write!(f, "the view width {width} is not positive")
+            }
+            CameraError::InvalidLens {
+                aperture,
+                focus_distance,
+            } => write!(
+                f,
+                "the lens with an aperture of {aperture} and a focus distance of \
+                 {focus_distance} is invalid: the aperture must be positive and the focus \
+                 distance strictly positive"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CameraError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    fn assert_close(a: Vector, b: Vector) {
+        assert!((a - b).square_norm() < 1e-10, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_look_at() {
+        let camera = Camera::look_at(
+            vector(0., 0., 55.),
+            vector(0., 0., 0.),
+            vector(0., -2., 1.),
+            Fov::Horizontal(PI / 2.),
+        )
+        .unwrap();
+        assert_close(camera.direction, vector(0., 0., -1.));
+        assert_close(camera.up, vector(0., -1., 0.));
+        assert_close(camera.right, vector(1., 0., 0.));
+        assert_eq!(
+            camera.projection,
+            Projection::Perspective {
+                fov: Fov::Horizontal(PI / 2.)
+            }
+        );
+
+        // the rays through the top left corner go up and left
+        let mut rng = StdRng::seed_from_u64(0);
+        let ray = camera.generate_ray(0, 0, (640, 480), &mut rng).unwrap();
+        assert!(ray.direction.x < 0. && ray.direction.y < 0. && ray.direction.z < 0.);
+    }
+
+    #[test]
+    fn test_degenerate_up() {
+        for up in [vector(0., 0., 0.), vector(0., 3., 0.)] {
+            let camera = Camera::look_at(
+                vector(0., 0., 0.),
+                vector(0., -5., 0.),
+                up,
+                Fov::Horizontal(1.),
+            )
+            .unwrap();
+            let basis = [camera.direction, camera.up, camera.right];
+            for (i, a) in basis.iter().enumerate() {
+                assert!((a.square_norm() - 1.).abs() < 1e-5);
+                for b in &basis[i + 1..] {
+                    assert!(a.dot(b).abs() < 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_cameras() {
+        let origin = vector(0., 0., 0.);
+        let fov = Fov::Horizontal(1.);
+        assert_eq!(
+            Camera::look_at(origin, origin, vector(0., 1., 0.), fov).err(),
+            Some(CameraError::TargetAtEye)
+        );
+        assert_eq!(
+            Camera::look_at(origin, vector(f32::NAN, 0., 1.), vector(0., 1., 0.), fov).err(),
+            Some(CameraError::InvalidVector("target"))
+        );
+        let (direction, up, right) = (vector(0., 0., 1.), vector(0., 1., 0.), vector(1., 0., 0.));
+        let perspective = Projection::Perspective {
+            fov: Fov::Horizontal(1.),
+        };
+        assert!(Camera::new(origin, direction, up, right, perspective).is_ok());
+        assert_eq!(
+            Camera::new(origin, direction, up, vector(1., 1., 0.), perspective).err(),
+            Some(CameraError::NotOrthogonal)
+        );
+        assert_eq!(
+            Camera::new(origin, direction, origin, right, perspective).err(),
+            Some(CameraError::InvalidVector("up"))
+        );
+        let invalid_projections = [
+            Projection::Perspective {
+                fov: Fov::Horizontal(-1.),
+            },
+            Projection::Perspective {
+                fov: Fov::Horizontal(4.),
+            },
+            Projection::Fisheye {
+                fov: Fov::Horizontal(7.),
+            },
+            Projection::Orthographic { width: 0. },
+        ];
+        for projection in invalid_projections {
+            assert!(Camera::new(origin, direction, up, right, projection).is_err());
+        }
+        assert!(Camera::new(
+            origin,
+            direction,
+            up,
+            right,
+            Projection::Fisheye {
+                fov: Fov::Horizontal(4.)
+            }
+        )
+        .is_ok());
+    }
+
+    /// A camera at the origin looking towards z, with x on the right and y at the top
+    fn camera(projection: Projection) -> Camera {
+        Camera::look_at_with(
+            vector(0., 0., 0.),
+            vector(0., 0., 1.),
+            vector(0., 1., 0.),
+            projection,
+        )
+        .unwrap()
+    }
+
+    /// Return the average direction of the rays through a pixel of a 200×100 image, which
+    /// removes the jitter, or `None` if the projection does not cover the pixel
+    fn direction(camera: &Camera, (x, y): (u32, u32)) -> Option<Vector> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let rays: Vec<Ray> = (0..1000)
+            .filter_map(|_| camera.generate_ray(x, y, (200, 100), &mut rng))
+            .collect();
+        let sum = rays
+            .iter()
+            .fold(vector(0., 0., 0.), |sum, ray| sum + ray.direction);
+        (!rays.is_empty()).then(|| sum.normalize())
+    }
+
+    fn assert_direction(camera: &Camera, pixel: (u32, u32), expected: Vector) {
+        let direction = direction(camera, pixel).unwrap();
+        assert!(
+            direction.dot(&expected) > 0.99,
+            "{direction:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_orthographic() {
+        let camera = camera(Projection::Orthographic { width: 20. });
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let ray = camera.generate_ray(0, 0, (200, 100), &mut rng).unwrap();
+            assert_eq!(ray.direction, vector(0., 0., 1.));
+            // the top left corner is at (-10, 5), up to the jitter of a few pixels
+            assert!((ray.origin.x + 10.).abs() < 0.5 && (ray.origin.y - 5.).abs() < 0.5);
+        }
+    }
+
+    #[test]
+    fn test_equirectangular() {
+        let camera = camera(Projection::Equirectangular);
+        // the longitude spans the width, from the back to the back, and the latitude the
+        // height, from the top to the bottom
+        assert_direction(&camera, (100, 50), vector(0., 0., 1.));
+        assert_direction(&camera, (150, 50), vector(1., 0., 0.));
+        assert_direction(&camera, (0, 50), vector(0., 0., -1.));
+        assert_direction(&camera, (100, 0), vector(0., 1., 0.));
+        assert_direction(&camera, (100, 99), vector(0., -1., 0.));
+    }
+
+    #[test]
+    fn test_fisheye() {
+        // a half sphere spans the width of the image
+        let camera = camera(Projection::Fisheye {
+            fov: Fov::Horizontal(PI),
+        });
+        assert_direction(&camera, (100, 50), vector(0., 0., 1.));
+        assert_direction(&camera, (0, 50), vector(-1., 0., 0.));
+        assert_direction(&camera, (100, 0), vector(0., 1., 1.).normalize());
+        // a full sphere reaches backwards, and leaves the corners black
+        let camera = Camera {
+            projection: Projection::Fisheye {
+                fov: Fov::Horizontal(2. * PI),
+            },
+            ..camera
+        };
+        assert_direction(&camera, (50, 50), vector(-1., 0., 0.));
+        assert!(direction(&camera, (0, 0)).is_none());
+    }
+
+    #[test]
+    fn test_vertical_fov() {
+        // the angle spans the height of the image, whatever its width
+        let perspective = camera(Projection::Perspective {
+            fov: Fov::Vertical(PI / 2.),
+        });
+        assert_direction(&perspective, (100, 0), vector(0., 1., 1.).normalize());
+        assert_direction(&perspective, (0, 50), vector(-2., 0., 1.).normalize());
+        let fisheye = camera(Projection::Fisheye {
+            fov: Fov::Vertical(PI),
+        });
+        assert_direction(&fisheye, (100, 0), vector(0., 1., 0.));
+        assert_direction(&fisheye, (50, 50), vector(-1., 0., 0.));
+    }
+
+    #[test]
+    fn test_lens_sample() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let round = Lens {
+            aperture: 2.,
+            focus_distance: 10.,
+            blades: 0,
+            rotation: 0.,
+        };
+        let hexagon = Lens {
+            blades: 6,
+            rotation: 0.1,
+            ..round
+        };
+        for _ in 0..1000 {
+            let (x, y) = round.sample(&mut rng);
+            assert!(x.hypot(y) <= 2. + 1e-5);
+            // the edges of the hexagon are at 2 cos(π / 6) from its center, facing the
+            // middles of its corners
+            let (x, y) = hexagon.sample(&mut rng);
+            for edge in 0..6 {
+                let angle = 0.1 + PI / 6. + edge as f32 * PI / 3.;
+                assert!(x * angle.cos() + y * angle.sin() <= 2. * (PI / 6.).cos() + 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_lens() {
+        assert!(Lens::new(0., 10., 0, 0.).is_ok());
+        for (aperture, focus_distance) in [(-1., 10.), (f32::NAN, 10.), (1., 0.), (1., -10.)] {
+            assert!(matches!(
+                Lens::new(aperture, focus_distance, 0, 0.),
+                Err(CameraError::InvalidLens { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_depth_of_field() {
+        let mut camera = Camera::look_at(
+            vector(0., 0., 55.),
+            vector(0., 0., 0.),
+            vector(0., 1., 0.),
+            Fov::Horizontal(PI / 2.),
+        )
+        .unwrap();
+        camera.lens = Some(Lens {
+            aperture: 5.,
+            focus_distance: 30.,
+            blades: 0,
+            rotation: 0.,
+        });
+        let mut rng = StdRng::seed_from_u64(0);
+        let rays: Vec<Ray> = (0..100)
+            .map(|_| camera.generate_ray(320, 240, (640, 480), &mut rng).unwrap())
+            .collect();
+        // the rays leave from the whole lens, but converge on the plane in focus, 30 units
+        // in front of the camera
+        let spread = |points: &[Vector]| {
+            points
+                .iter()
+                .flat_map(|a| points.iter().map(move |b| (*a - *b).square_norm().sqrt()))
+                .fold(0., f32::max)
+        };
+        let origins: Vec<Vector> = rays.iter().map(|ray| ray.origin).collect();
+        let focused: Vec<Vector> = rays
+            .iter()
+            .map(|ray| ray.origin + ray.direction * ((ray.origin.z - 25.) / -ray.direction.z))
+            .collect();
+        assert!(spread(&origins) > 5.);
+        assert!(spread(&focused) < 0.5);
+    }
+}
@@ -1,5 +1,8 @@
-pub const IMAGE_SIZE: (u32, u32) = (640, 480);
-pub const MAX_BOUNCES: u8 = 1;
-pub const DIFFUSED: bool = true;
-pub const DIFFUSED_SAMPLES_COUNT: u32 = 500;
-pub const BLACK: [f32; 3] = [0., 0., 0.];
+pub const TILE_SIZE: u32 = 32;
+
+/// The number of bounces of a path after which it may be terminated by Russian roulette
+pub const RUSSIAN_ROULETTE_BOUNCES: u8 = 3;
+
+/// The roughness below which glossy reflections are traced like mirror reflections, rather
+/// than estimated by sampling the lights
+pub const SMOOTH_ROUGHNESS: f32 = 0.1;
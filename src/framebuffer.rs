@@ -0,0 +1,191 @@
+use crate::utils::color::Color;
+use image::{Rgb, Rgb32FImage, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A floating-point image, holding the linear color computed for every pixel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    /// the pixels, row by row
+    pixels: Vec<Color>,
+}
+
+impl Framebuffer {
+    /// Create a black framebuffer
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::BLACK; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The pixels, row by row
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn put_pixel(&mut self, x: u32, y: u32, color: Color) {
+        let index = self.index(x, y);
+        self.pixels[index] = color;
+    }
+
+    /// Return the framebuffer whose pixels are transformed by a function
+    pub fn map<F: Fn(Color) -> Color>(&self, f: F) -> Framebuffer {
+        Framebuffer {
+            pixels: self.pixels.iter().map(|&color| f(color)).collect(),
+            ..*self
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        y as usize * self.width as usize + x as usize
+    }
+
+    /// Convert the framebuffer into an 8-bit sRGB image. The colors are scaled by 2 to the
+    /// power of the exposure, in stops, then brought into the displayable range by the tone
+    /// mapping.
+    pub fn to_rgb8(&self, exposure: f32, tone_mapping: ToneMapping) -> RgbImage {
+        let scale = exposure.exp2();
+        RgbImage::from_fn(self.width, self.height, |x, y| {
+            let color = tone_mapping
+                .apply(self.get_pixel(x, y) * scale)
+                .encode_srgb();
+            Rgb(color.to_array().map(|value| (value * 255.).round() as u8))
+        })
+    }
+
+    /// Convert the framebuffer into a floating-point image of its linear colors
+    pub fn to_rgb32f(&self) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.width, self.height, |x, y| {
+            Rgb(self.get_pixel(x, y).to_array())
+        })
+    }
+}
+
+/// The curves mapping the unbounded linear colors to the range of the displays
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneMapping {
+    /// The channels above 1 are clipped, which keeps the colors of the scene but burns the
+    /// highlights
+    #[default]
+    Clamp,
+    /// `x / (1 + x)`, which compresses the highlights smoothly but dulls the image
+    Reinhard,
+    /// Narkowicz's fit of the filmic curve of the Academy Color Encoding System, which gives
+    /// contrast to the midtones and rolls off the highlights
+    Aces,
+}
+
+impl ToneMapping {
+    pub const ALL: [ToneMapping; 3] =
+        [ToneMapping::Clamp, ToneMapping::Reinhard, ToneMapping::Aces];
+
+    /// The name of the tone mapping, in scene files and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToneMapping::Clamp => "clamp",
+            ToneMapping::Reinhard => "reinhard",
+            ToneMapping::Aces => "aces",
+        }
+    }
+
+    /// Map a linear color to a color whose channels are between 0 and 1
+    pub fn apply(&self, color: Color) -> Color {
+        color.map(|value| {
+            let value = value.max(0.);
+            let mapped = match self {
+                ToneMapping::Clamp => value,
+                ToneMapping::Reinhard => value / (1. + value),
+                ToneMapping::Aces => {
+                    value * (2.51 * value + 0.03) / (value * (2.43 * value + 0.59) + 0.14)
+                }
+            };
+            mapped.min(1.)
+        })
+    }
+}
+
+impl fmt::Display for ToneMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ToneMapping {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|tone_mapping| tone_mapping.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(ToneMapping::name).collect();
+                format!(
+                    "unknown tone mapping `{name}`, expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_pixel() {
+        let mut framebuffer = Framebuffer::new(3, 2);
+        let color = Color::from([1., 2., 3.]);
+        framebuffer.put_pixel(2, 1, color);
+        assert_eq!(framebuffer.get_pixel(2, 1), color);
+        assert_eq!(framebuffer.pixels()[5], color);
+    }
+
+    #[test]
+    fn test_to_rgb8() {
+        let mut framebuffer = Framebuffer::new(1, 1);
+        framebuffer.put_pixel(0, 0, Color::from([0., 0.18, 1_000_000.]));
+        let pixel =
+            |exposure, tone_mapping| *framebuffer.to_rgb8(exposure, tone_mapping).get_pixel(0, 0);
+        assert_eq!(pixel(0., ToneMapping::Clamp), Rgb([0, 118, 255]));
+        // one stop doubles the light
+        assert_eq!(pixel(1., ToneMapping::Clamp), Rgb([0, 162, 255]));
+        assert_eq!(pixel(0., ToneMapping::Reinhard), Rgb([0, 109, 255]));
+        assert_eq!(pixel(0., ToneMapping::Aces), Rgb([0, 141, 255]));
+    }
+
+    #[test]
+    fn test_tone_mappings() {
+        for tone_mapping in ToneMapping::ALL {
+            assert_eq!(tone_mapping.name().parse(), Ok(tone_mapping));
+            assert_eq!(tone_mapping.apply(Color::BLACK), Color::BLACK);
+            // the curves are increasing, and bounded by 1
+            let mut previous = 0.;
+            for value in [0.01, 0.1, 0.5, 1., 2., 10., 1e6] {
+                let mapped = tone_mapping.apply(Color::gray(value)).r;
+                assert!(mapped >= previous && mapped <= 1., "{tone_mapping}");
+                previous = mapped;
+            }
+        }
+        assert_eq!(ToneMapping::Reinhard.apply(Color::WHITE), Color::gray(0.5));
+        assert!("filmic".parse::<ToneMapping>().is_err());
+    }
+}
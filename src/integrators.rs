@@ -0,0 +1,262 @@
+//! The algorithms estimating the light seen along the rays traced from the camera.
+//!
+//! The built-in integrators are selected by name with [`RenderSettings::integrator`]. Other
+//! integrators implementing [`Integrator`] can be rendered with [`Scene::render_with`].
+use crate::materials::Shading;
+use crate::scene::Scene;
+use crate::settings::{IntegratorKind, RenderSettings};
+use crate::utils::color::Color;
+use crate::utils::intersection::Intersection;
+use crate::utils::ray::Ray;
+use crate::utils::vector::Vector;
+use rand::{Rng, RngCore};
+use std::f32::consts::PI;
+
+mod ambient_occlusion;
+mod normals;
+mod path;
+mod whitted;
+
+pub use ambient_occlusion::AmbientOcclusion;
+pub use normals::Normals;
+pub use path::PathTracer;
+pub use whitted::Whitted;
+
+pub trait Integrator: Send + Sync {
+    /// Return the light arriving at the origin of a ray, in linear color
+    fn radiance(&self, scene: &Scene, ray: &Ray, rng: &mut dyn RngCore) -> Color;
+
+    /// Return the light arriving at the origin of a ray split into its direct part, coming
+    /// straight from the lights or emitted or reflected from the lights by the first surface
+    /// hit, and its indirect part, which bounced off other surfaces. Their sum is the
+    /// radiance, which is direct by default.
+    fn split_radiance(&self, scene: &Scene, ray: &Ray, rng: &mut dyn RngCore) -> (Color, Color) {
+        (self.radiance(scene, ray, rng), Color::BLACK)
+    }
+
+    /// Return the number of rays averaged per pixel
+    fn samples_count(&self) -> u32 {
+        1
+    }
+}
+
+/// Build the integrator selected by the settings
+pub fn from_settings(settings: &RenderSettings) -> Box<dyn Integrator> {
+    match settings.integrator {
+        IntegratorKind::Whitted => Box::new(Whitted {
+            max_bounces: settings.max_bounces,
+            diffused_samples_count: if settings.diffused {
+                settings.diffused_samples_count
+            } else {
+                0
+            },
+        }),
+        IntegratorKind::Path => Box::new(PathTracer {
+            max_bounces: settings.max_bounces,
+            samples_count: settings.diffused_samples_count.max(1),
+        }),
+        IntegratorKind::AmbientOcclusion => Box::new(AmbientOcclusion {
+            distance: settings.occlusion_distance,
+            samples_count: settings.diffused_samples_count.max(1),
+        }),
+        IntegratorKind::Normals => Box::new(Normals),
+    }
+}
+
+/// Return the linear value of a channel displayed with a fraction of the full brightness,
+/// without exposure nor tone mapping
+pub(crate) fn displayed(fraction: f32) -> f32 {
+    Color::gray(fraction).decode_srgb().r
+}
+
+/// Return a ray leaving a surface in a random direction, following a cosine-weighted
+/// distribution around its normal
+pub(crate) fn diffuse(intersection: &Intersection, rng: &mut dyn RngCore) -> Ray {
+    let direction = {
+        let intersect_dir_local = {
+            let r1: f32 = rng.random_range(0.0..1.);
+            let r2: f32 = rng.random_range(0.0..1.);
+            Vector {
+                x: (2. * PI * r1).cos() * (1. - r2).sqrt(),
+                y: (2. * PI * r1).sin() * (1. - r2).sqrt(),
+                z: r2.sqrt(),
+            }
+        };
+        let tangent_1 = {
+            let random_vec = Vector {
+                x: rng.random_range(0.0..1.),
+                y: rng.random_range(0.0..1.),
+                z: rng.random_range(0.0..1.),
+            };
+            intersection.normal.cross(&random_vec).normalize()
+        };
+        let tangent_2 = intersection.normal.cross(&tangent_1);
+
+        (tangent_1 * intersect_dir_local.x
+            + tangent_2 * intersect_dir_local.y
+            + intersection.normal * intersect_dir_local.z)
+            .normalize()
+    };
+    Ray::new(intersection.intersection, direction)
+}
+
+/// Return a ray reflected by a random microfacet of a glossy surface, seen from the direction
+/// `view`, with the fraction of each channel it carries, or none when the reflection goes
+/// below the surface
+pub(crate) fn glossy(
+    intersection: &Intersection,
+    shading: &Shading,
+    view: &Vector,
+    rng: &mut dyn RngCore,
+) -> Option<(Ray, Color)> {
+    let microfacet = shading.microfacet?;
+    let normal = intersection.normal;
+    let half = microfacet.sample_normal(normal, rng);
+    let cos_half_view = view.dot(&half);
+    let direction = (half * (2. * cos_half_view) - *view).normalize();
+    let (cos_view, cos_light) = (normal.dot(view), normal.dot(&direction));
+    if cos_half_view <= 0. || cos_view <= 0. || cos_light <= 0. {
+        return None;
+    }
+    // the density of the sampled direction, D·cos_half / (4·cos_half_view), cancels the
+    // distribution of the microfacets, which is too peaked on smooth surfaces to be evaluated
+    let factor =
+        microfacet.shadowing(cos_view, cos_light) * cos_half_view / (cos_view * normal.dot(&half));
+    let weight = shading.fresnel(cos_half_view) * factor;
+    Some((Ray::new(intersection.intersection, direction), weight))
+}
+
+/// Return the intersection of a ray with a random microfacet of a rough transparent surface,
+/// whose reflection and refraction are blurred, or with the surface itself when it is smooth
+pub(crate) fn rough_interface<'a>(
+    intersection: &Intersection<'a>,
+    ray: &Ray,
+    rng: &mut dyn RngCore,
+) -> Intersection<'a> {
+    let shading = intersection.shape.get_material().shading(intersection.uv);
+    let normal = match shading.microfacet {
+        Some(microfacet) => {
+            let normal = microfacet.sample_normal(intersection.normal, rng);
+            // a microfacet facing away from the ray would swap the media
+            let same_side =
+                (normal.dot(&ray.direction) < 0.) == (intersection.normal.dot(&ray.direction) < 0.);
+            if same_side {
+                normal
+            } else {
+                intersection.normal
+            }
+        }
+        None => intersection.normal,
+    };
+    Intersection {
+        normal,
+        ..*intersection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Material;
+    use crate::shapes::{Plane, Shape};
+    use crate::textures::Constant;
+    use rand::SeedableRng;
+    use std::sync::Arc;
+
+    /// Return the average of the first channel of the radiance along a ray, over many samples
+    pub(crate) fn average_radiance(scene: &Scene, integrator: &dyn Integrator, ray: &Ray) -> f32 {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let samples_count = 1000;
+        let total: f32 = (0..samples_count)
+            .map(|_| integrator.radiance(scene, ray, &mut rng).r)
+            .sum();
+        total / samples_count as f32
+    }
+
+    #[test]
+    fn test_from_settings() {
+        for kind in IntegratorKind::ALL {
+            let settings = RenderSettings {
+                integrator: kind,
+                diffused_samples_count: 8,
+                ..RenderSettings::default()
+            };
+            let expected = if kind == IntegratorKind::Normals {
+                1
+            } else {
+                8
+            };
+            assert_eq!(from_settings(&settings).samples_count(), expected);
+        }
+        let settings = RenderSettings {
+            diffused: false,
+            ..RenderSettings::default()
+        };
+        assert_eq!(from_settings(&settings).samples_count(), 1);
+    }
+
+    #[test]
+    fn test_glossy_sampling() {
+        let normal = Vector {
+            x: 0.,
+            y: 1.,
+            z: 0.,
+        };
+        let metal = |roughness| Plane {
+            origin: Vector {
+                x: 0.,
+                y: 0.,
+                z: 0.,
+            },
+            normal,
+            material: Material {
+                roughness: Some(Arc::new(Constant(Color::gray(roughness)))),
+                metallic: Some(Arc::new(Constant(Color::gray(1.)))),
+                ..Material::diffuse(Color::WHITE)
+            },
+        };
+        let view = Vector {
+            x: -0.6,
+            y: 0.8,
+            z: 0.,
+        };
+        let ray = Ray::new(view, view * -1.);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let samples_count = 20_000;
+        for roughness in [0.05, 0.5] {
+            let plane = metal(roughness);
+            let intersection = plane.get_intersection(&ray).unwrap();
+            let shading = plane.material.shading(intersection.uv);
+            // the fraction of the light reflected, estimated by importance sampling
+            let sampled: f32 = (0..samples_count)
+                .filter_map(|_| glossy(&intersection, &shading, &view, &mut rng))
+                .map(|(_, weight)| weight.r)
+                .sum::<f32>()
+                / samples_count as f32;
+            // and by sampling the hemisphere uniformly
+            let uniform: f32 = (0..samples_count)
+                .map(|_| {
+                    let z: f32 = rng.random_range(0.0..1.);
+                    let phi = rng.random_range(0.0..2. * PI);
+                    let r = (1. - z * z).sqrt();
+                    let light = Vector {
+                        x: r * phi.cos(),
+                        y: z,
+                        z: r * phi.sin(),
+                    };
+                    2. * shading.glossy(&normal, &view, &light).r * z
+                })
+                .sum::<f32>()
+                / samples_count as f32;
+            assert!(sampled <= 1.);
+            if roughness > 0.1 {
+                assert!(
+                    (sampled / uniform - 1.).abs() < 0.05,
+                    "{sampled} != {uniform}"
+                );
+            } else {
+                assert!(sampled > 0.95);
+            }
+        }
+    }
+}
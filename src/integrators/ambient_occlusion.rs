@@ -0,0 +1,66 @@
+use crate::integrators::{diffuse, displayed, Integrator};
+use crate::scene::Scene;
+use crate::utils::color::Color;
+use crate::utils::ray::Ray;
+use rand::RngCore;
+
+/// Ambient occlusion: the surfaces are shaded by the fraction of the hemisphere above them
+/// which is not occluded by other shapes within a distance, weighted by the cosine
+pub struct AmbientOcclusion {
+    pub distance: f32,
+    /// the number of rays averaged per pixel, each testing one random direction
+    pub samples_count: u32,
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, scene: &Scene, ray: &Ray, rng: &mut dyn RngCore) -> Color {
+        let Some(intersection) = scene.intersect(ray) else {
+            return Color::BLACK;
+        };
+        let occlusion_ray = Ray {
+            t_max: self.distance,
+            ..diffuse(&intersection, rng)
+        };
+        if scene.intersect(&occlusion_ray).is_some() {
+            Color::BLACK
+        } else {
+            Color::gray(displayed(1.))
+        }
+    }
+
+    fn samples_count(&self) -> u32 {
+        self.samples_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_scene;
+    use crate::integrators::tests::average_radiance;
+    use crate::utils::vector::Vector;
+
+    #[test]
+    fn test_ambient_occlusion() {
+        let scene = example_scene();
+        let integrator = AmbientOcclusion {
+            distance: 1.,
+            samples_count: 1,
+        };
+        // the floor is only occluded near the walls
+        let ray = |x: f32| {
+            Ray::new(
+                Vector { x, y: 0., z: 50. },
+                Vector {
+                    x: 0.,
+                    y: 1.,
+                    z: 0.,
+                },
+            )
+        };
+        let open = average_radiance(&scene, &integrator, &ray(0.)) / displayed(1.);
+        assert!((open - 1.).abs() < 1e-3);
+        let corner = average_radiance(&scene, &integrator, &ray(59.5)) / displayed(1.);
+        assert!(corner > 0.5 && corner < 0.9, "{corner}");
+    }
+}
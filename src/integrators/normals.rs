@@ -0,0 +1,52 @@
+use crate::integrators::{displayed, Integrator};
+use crate::scene::Scene;
+use crate::utils::color::Color;
+use crate::utils::ray::Ray;
+use rand::RngCore;
+
+/// Shows the normals of the surfaces, for debugging: the x, y and z components, from -1 to 1,
+/// are mapped to the red, green and blue channels
+pub struct Normals;
+
+impl Integrator for Normals {
+    fn radiance(&self, scene: &Scene, ray: &Ray, _rng: &mut dyn RngCore) -> Color {
+        match scene.intersect(ray) {
+            Some(intersection) => {
+                let normal = intersection.normal;
+                Color::from([normal.x, normal.y, normal.z])
+                    .map(|component| displayed((component + 1.) / 2.))
+            }
+            None => Color::BLACK,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_scene;
+    use crate::utils::vector::Vector;
+
+    #[test]
+    fn test_normals() {
+        let scene = example_scene();
+        let ray = Ray::new(
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: 55.,
+            },
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: -1.,
+            },
+        );
+        // the front of the sphere faces +z
+        let color = Normals.radiance(&scene, &ray, &mut rand::rng());
+        assert_eq!(
+            color,
+            Color::from([displayed(0.5), displayed(0.5), displayed(1.)])
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use crate::constants::RUSSIAN_ROULETTE_BOUNCES;
+use crate::integrators::{diffuse, glossy, rough_interface, Integrator};
+use crate::scene::Scene;
+use crate::utils::color::Color;
+use crate::utils::ray::Ray;
+use rand::{Rng, RngCore};
+
+/// Unbiased Monte Carlo path tracing.
+///
+/// The lights are sampled at each diffuse or rough glossy bounce (next-event estimation), and
+/// the paths are randomly terminated once their contribution becomes small (Russian roulette),
+/// which keeps the estimate unbiased.
+pub struct PathTracer {
+    pub max_bounces: u8,
+    /// the number of paths averaged per pixel
+    pub samples_count: u32,
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, scene: &Scene, ray: &Ray, rng: &mut dyn RngCore) -> Color {
+        let (direct, indirect) = self.split_radiance(scene, ray, rng);
+        direct + indirect
+    }
+
+    fn split_radiance(&self, scene: &Scene, ray: &Ray, rng: &mut dyn RngCore) -> (Color, Color) {
+        let mut ray = *ray;
+        let (mut direct, mut indirect) = (Color::BLACK, Color::BLACK);
+        // the fraction of the light reaching the camera from the current bounce
+        let mut throughput = Color::WHITE;
+        // the lights are only seen by the camera and through specular or smooth glossy bounces,
+        // as they are sampled at the other bounces
+        let mut visible_lights = true;
+        for bounce in 0..=self.max_bounces {
+            // the light gathered at the first surface hit is direct
+            let color = if bounce == 0 {
+                &mut direct
+            } else {
+                &mut indirect
+            };
+            let intersection = scene.intersect(&ray);
+            if visible_lights {
+                let t_max = intersection.as_ref().map_or(ray.t_max, |i| i.d);
+                if let Some(radiance) = scene.get_light_radiance(&Ray { t_max, ..ray }) {
+                    *color += throughput * radiance;
+                    break;
+                }
+            }
+            let Some(intersection) = intersection else {
+                break;
+            };
+            let material = intersection.shape.get_material();
+            *color += throughput * material.emission;
+
+            if material.specular && bounce < self.max_bounces {
+                ray.reflect(&intersection);
+                visible_lights = true;
+                continue;
+            }
+            if material.refractive_index != 0. && bounce < self.max_bounces {
+                if ray.is_leaving(&intersection) {
+                    throughput *= material.transmittance(intersection.d);
+                }
+                // following either the reflection or the refraction, with the fraction of the
+                // light it carries as probability, keeps the estimate unbiased
+                let interface = rough_interface(&intersection, &ray, rng);
+                if rng.random::<f32>() < ray.reflectance(&interface) {
+                    ray.reflect(&interface);
+                } else {
+                    ray.refract(&interface);
+                }
+                visible_lights = true;
+                continue;
+            }
+            let view = ray.direction * -1.;
+            *color += throughput * scene.get_direct_light(&intersection, &view, rng);
+            if bounce == self.max_bounces {
+                break;
+            }
+
+            // the diffuse and glossy reflections are each estimated by sampling their own
+            // distribution, one of them being picked at random
+            let shading = material.shading(intersection.uv);
+            let glossy_probability = shading
+                .microfacet
+                .map_or(0., |microfacet| (1. + microfacet.metallic) / 2.);
+            let weight = if glossy_probability > 0. && rng.random::<f32>() < glossy_probability {
+                let Some((reflected, weight)) = glossy(&intersection, &shading, &view, rng) else {
+                    break;
+                };
+                ray = reflected;
+                // the lights are only sampled for the rough reflections
+                visible_lights = shading
+                    .microfacet
+                    .is_some_and(|microfacet| microfacet.is_smooth());
+                weight / glossy_probability
+            } else {
+                // the cosine-weighted sampling of the bounce cancels the cosine and the 1/π
+                // of the lambertian reflection, leaving the diffuse color of the material
+                ray = diffuse(&intersection, rng);
+                visible_lights = false;
+                shading.diffuse(&intersection.normal, &view) / (1. - glossy_probability)
+            };
+            throughput *= weight;
+            if bounce >= RUSSIAN_ROULETTE_BOUNCES {
+                let survival = throughput.max_channel().min(0.95);
+                if rng.random::<f32>() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+        (direct, indirect)
+    }
+
+    fn samples_count(&self) -> u32 {
+        self.samples_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_scene;
+    use crate::lights::{Light, RectangleLight};
+    use crate::materials::Material;
+    use crate::shapes::{Plane, Quad, Shape};
+    use crate::utils::vector::Vector;
+    use rand::prelude::*;
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    fn material(color: f32, emission: f32) -> Material {
+        Material {
+            emission: Color::gray(emission),
+            ..Material::diffuse(Color::gray(color))
+        }
+    }
+
+    /// Return the average color of the paths starting above a white floor lit from above
+    fn average_floor_color(shapes: Vec<Box<dyn Shape>>, lights: Vec<Box<dyn Light>>) -> f32 {
+        let scene = example_scene();
+        let scene = Scene::new(shapes, lights, scene.camera, scene.settings);
+        let integrator = PathTracer {
+            max_bounces: 1,
+            samples_count: 1,
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let ray = Ray::new(vector(0., 0.5, 0.), vector(0., -1., 0.));
+        let samples_count = 20_000;
+        let total: f32 = (0..samples_count)
+            .map(|_| integrator.radiance(&scene, &ray, &mut rng).r)
+            .sum();
+        total / samples_count as f32
+    }
+
+    #[test]
+    fn test_emission_matches_area_light() {
+        let floor = || -> Box<dyn Shape> {
+            Box::new(Plane {
+                origin: vector(0., 0., 0.),
+                normal: vector(0., 1., 0.),
+                material: material(1., 0.),
+            })
+        };
+        let (origin, edge_1, edge_2) = (
+            vector(-0.5, 1., -0.5),
+            vector(1., 0., 0.),
+            vector(0., 0., 1.),
+        );
+        let emissive_quad: Box<dyn Shape> = Box::new(Quad {
+            origin,
+            edge_1,
+            edge_2,
+            material: material(0., 100.),
+        });
+        let light: Box<dyn Light> = Box::new(RectangleLight {
+            origin,
+            edge_1,
+            edge_2,
+            // the intensity whose radiance is an emission of 100
+            intensity: Color::gray(2. * 100.),
+            samples_count: 1,
+        });
+
+        let emitted = average_floor_color(vec![floor(), emissive_quad], Vec::new());
+        let sampled = average_floor_color(vec![floor()], vec![light]);
+        assert!(sampled > 0.);
+        assert!(
+            (emitted / sampled - 1.).abs() < 0.05,
+            "{emitted} != {sampled}"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use crate::integrators::{diffuse, glossy, rough_interface, Integrator};
+use crate::scene::Scene;
+use crate::utils::color::Color;
+use crate::utils::ray::Ray;
+use rand::RngCore;
+
+/// Whitted-style ray tracing: reflections, refractions and direct lighting, to which a single
+/// diffuse bounce may be added, traced once per ray and averaged over the rays of a pixel.
+/// Emissive materials are visible but do not light the scene.
+pub struct Whitted {
+    pub max_bounces: u8,
+    /// the number of rays per pixel estimating the diffuse bounce, which is skipped when 0
+    pub diffused_samples_count: u32,
+}
+
+impl Integrator for Whitted {
+    fn radiance(&self, scene: &Scene, ray: &Ray, rng: &mut dyn RngCore) -> Color {
+        let (direct, indirect) = self.split_radiance(scene, ray, rng);
+        direct + indirect
+    }
+
+    fn split_radiance(&self, scene: &Scene, ray: &Ray, rng: &mut dyn RngCore) -> (Color, Color) {
+        let (mut direct, mut indirect) = get_color(scene, *ray, self.max_bounces, false, true, rng);
+        if self.diffused_samples_count > 0 {
+            let (diffused_direct, diffused_indirect) = get_color(scene, *ray, 1, true, false, rng);
+            direct += diffused_direct;
+            indirect += diffused_indirect;
+        }
+        (direct, indirect)
+    }
+
+    fn samples_count(&self) -> u32 {
+        self.diffused_samples_count.max(1)
+    }
+}
+
+/// Return the color seen along a ray, split into the direct light of the first surface hit
+/// and the indirect light. The surfaces of the lights are only visible when `visible_lights`
+/// is set, so that the light they cast on diffuse surfaces, which is sampled directly, is not
+/// counted twice.
+fn get_color(
+    scene: &Scene,
+    mut ray: Ray,
+    remaining_bounces: u8,
+    diffused: bool,
+    visible_lights: bool,
+    rng: &mut dyn RngCore,
+) -> (Color, Color) {
+    let intersection = scene.intersect(&ray);
+    if visible_lights {
+        let t_max = intersection.as_ref().map_or(ray.t_max, |i| i.d);
+        if let Some(radiance) = scene.get_light_radiance(&Ray { t_max, ..ray }) {
+            return (radiance, Color::BLACK);
+        }
+    }
+    let intersection = match intersection {
+        Some(intersection) => intersection,
+        None => return (Color::BLACK, Color::BLACK),
+    };
+
+    let material = intersection.shape.get_material();
+    if material.specular && remaining_bounces > 0 {
+        ray.reflect(&intersection);
+        let reflection = total(get_color(
+            scene,
+            ray,
+            remaining_bounces - 1,
+            diffused,
+            visible_lights,
+            rng,
+        ));
+        return (Color::BLACK, reflection);
+    }
+    if material.refractive_index != 0. && remaining_bounces > 0 {
+        // the light travelling inside the material is partly absorbed
+        let transmittance = if ray.is_leaving(&intersection) {
+            material.transmittance(intersection.d)
+        } else {
+            Color::WHITE
+        };
+        let interface = rough_interface(&intersection, &ray, rng);
+        let reflectance = ray.reflectance(&interface);
+        let mut reflected = ray;
+        reflected.reflect(&interface);
+        let mut refracted = ray;
+        refracted.refract(&interface);
+        let mut color = Color::BLACK;
+        for (fraction, ray) in [(reflectance, reflected), (1. - reflectance, refracted)] {
+            if fraction <= 0. {
+                continue;
+            }
+            color += transmittance
+                * fraction
+                * total(get_color(
+                    scene,
+                    ray,
+                    remaining_bounces - 1,
+                    diffused,
+                    visible_lights,
+                    rng,
+                ));
+        }
+        return (Color::BLACK, color);
+    }
+    let diffused_part = if diffused && remaining_bounces > 0 {
+        let ray = diffuse(&intersection, rng);
+        total(get_color(
+            scene,
+            ray,
+            remaining_bounces - 1,
+            diffused,
+            false,
+            rng,
+        ))
+    } else {
+        Color::BLACK
+    };
+    let view = ray.direction * -1.;
+    let mut direct = scene.get_direct_light(&intersection, &view, rng);
+    if visible_lights {
+        direct += material.emission;
+    }
+    let mut indirect = diffused_part;
+    let shading = material.shading(intersection.uv);
+    if let Some(microfacet) = shading.microfacet.filter(|_| remaining_bounces > 0) {
+        // a single reflection is traced, so the rough ones are noisy
+        if let Some((reflected, weight)) = glossy(&intersection, &shading, &view, rng) {
+            let reflection = total(get_color(
+                scene,
+                reflected,
+                remaining_bounces - 1,
+                diffused,
+                visible_lights && microfacet.is_smooth(),
+                rng,
+            ));
+            indirect += weight * reflection;
+        }
+    }
+    (direct, indirect)
+}
+
+/// Return the sum of the direct and indirect light
+fn total((direct, indirect): (Color, Color)) -> Color {
+    direct + indirect
+}
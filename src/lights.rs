@@ -0,0 +1,185 @@
+use crate::utils::color::Color;
+use crate::utils::ray::Ray;
+use crate::utils::vector::Vector;
+use rand::RngCore;
+use std::f32::consts::PI;
+use std::fmt::Debug;
+
+mod area;
+
+pub use area::{RectangleLight, SphereLight};
+
+/// The light received at a point from a light source
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LightSample {
+    /// the normalized direction from the point towards the light
+    pub direction: Vector,
+    /// the distance from the point to the light, which is infinite for directional lights
+    pub distance: f32,
+    /// the RGB intensity received at the point, before accounting for its orientation
+    pub intensity: Color,
+}
+
+pub trait Light: Send + Sync {
+    /// Return the light received at a point, ignoring the shapes which may shadow it, or
+    /// `None` if the point is not lit. Area lights return the light received from a random
+    /// point of their surface.
+    fn illuminate(&self, point: &Vector, rng: &mut dyn RngCore) -> Option<LightSample>;
+
+    /// Return the number of samples averaged to estimate the light received at a point
+    fn samples_count(&self) -> u32 {
+        1
+    }
+
+    /// Return the distance from the origin of a ray to the surface of the light, and the
+    /// radiance of the light, if the light has a visible surface
+    fn get_intersection(&self, _ray: &Ray) -> Option<(f32, Color)> {
+        None
+    }
+}
+
+impl Debug for dyn Light {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Light")
+    }
+}
+
+/// A light emitting in all directions from a point, decreasing with the square of the distance
+pub struct PointLight {
+    pub origin: Vector,
+    pub intensity: Color,
+}
+
+impl Light for PointLight {
+    fn illuminate(&self, point: &Vector, _rng: &mut dyn RngCore) -> Option<LightSample> {
+        let (direction, distance) = towards(point, &self.origin);
+        Some(LightSample {
+            direction,
+            distance,
+            intensity: attenuate(self.intensity, distance, 1.),
+        })
+    }
+}
+
+/// A light infinitely far away, such as the sun, lighting the whole scene from one direction
+pub struct DirectionalLight {
+    /// the normalized direction the light travels in
+    pub direction: Vector,
+    pub intensity: Color,
+}
+
+impl Light for DirectionalLight {
+    fn illuminate(&self, _point: &Vector, _rng: &mut dyn RngCore) -> Option<LightSample> {
+        Some(LightSample {
+            direction: self.direction * -1.,
+            distance: f32::INFINITY,
+            intensity: self.intensity,
+        })
+    }
+}
+
+/// A point light emitting in a cone. Its intensity decreases smoothly from the `falloff`
+/// angle to the `angle` of the cone.
+pub struct SpotLight {
+    pub origin: Vector,
+    /// the normalized direction of the axis of the cone
+    pub direction: Vector,
+    /// the angle between the axis and the border of the cone, in radians
+    pub angle: f32,
+    /// the angle between the axis and the start of the falloff, in radians
+    pub falloff: f32,
+    pub intensity: Color,
+}
+
+impl Light for SpotLight {
+    fn illuminate(&self, point: &Vector, _rng: &mut dyn RngCore) -> Option<LightSample> {
+        let (direction, distance) = towards(point, &self.origin);
+        let cos = -direction.dot(&self.direction);
+        let (cos_angle, cos_falloff) = (self.angle.cos(), self.falloff.cos());
+        if cos <= cos_angle {
+            return None;
+        }
+        let factor = if cos >= cos_falloff {
+            1.
+        } else {
+            let t = (cos - cos_angle) / (cos_falloff - cos_angle);
+            t * t * (3. - 2. * t)
+        };
+        Some(LightSample {
+            direction,
+            distance,
+            intensity: attenuate(self.intensity, distance, factor),
+        })
+    }
+}
+
+/// Return the normalized direction and the distance from a point to a light
+pub(crate) fn towards(point: &Vector, light: &Vector) -> (Vector, f32) {
+    let vector = *light - *point;
+    let distance = vector.square_norm().sqrt();
+    (vector * (1. / distance), distance)
+}
+
+/// Return the intensity received at a distance from a light, scaled by a factor
+pub(crate) fn attenuate(intensity: Color, distance: f32, factor: f32) -> Color {
+    intensity * (factor / (2. * PI * distance.powi(2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0)
+    }
+
+    #[test]
+    fn test_point_light() {
+        let light = PointLight {
+            origin: vector(0., 2., 0.),
+            intensity: Color::from([8. * PI, 0., 4. * PI]),
+        };
+        let sample = light.illuminate(&vector(0., 0., 0.), &mut rng()).unwrap();
+        assert_eq!(sample.direction, vector(0., 1., 0.));
+        assert_eq!(sample.distance, 2.);
+        assert_eq!(sample.intensity, Color::from([1., 0., 0.5]));
+    }
+
+    #[test]
+    fn test_directional_light() {
+        let light = DirectionalLight {
+            direction: vector(0., -1., 0.),
+            intensity: Color::WHITE,
+        };
+        let sample = light
+            .illuminate(&vector(100., 0., -3.), &mut rng())
+            .unwrap();
+        assert_eq!(sample.direction, vector(0., 1., 0.));
+        assert!(sample.distance.is_infinite());
+        assert_eq!(sample.intensity, Color::WHITE);
+    }
+
+    #[test]
+    fn test_spot_light() {
+        let light = SpotLight {
+            origin: vector(0., 0., 0.),
+            direction: vector(0., 0., 1.),
+            angle: PI / 4.,
+            falloff: PI / 8.,
+            intensity: Color::gray(2. * PI),
+        };
+        let center = light.illuminate(&vector(0., 0., 1.), &mut rng()).unwrap();
+        assert_eq!(center.intensity, Color::WHITE);
+        let border = light.illuminate(&vector(0.6, 0., 1.), &mut rng()).unwrap();
+        let distance_squared = 1.36;
+        assert!(border.intensity.r * distance_squared < 1.);
+        assert!(border.intensity.r > 0.);
+        assert!(light.illuminate(&vector(2., 0., 1.), &mut rng()).is_none());
+        assert!(light.illuminate(&vector(0., 0., -1.), &mut rng()).is_none());
+    }
+}
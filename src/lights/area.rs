@@ -0,0 +1,196 @@
+use crate::lights::{attenuate, towards, Light, LightSample};
+use crate::utils::color::Color;
+use crate::utils::ray::Ray;
+use crate::utils::vector::Vector;
+use rand::{Rng, RngCore};
+use std::f32::consts::PI;
+
+/// A spherical light, emitting from its whole surface. Its intensity is the one of a point
+/// light seen from afar.
+pub struct SphereLight {
+    pub origin: Vector,
+    pub radius: f32,
+    pub intensity: Color,
+    /// the number of points of the surface sampled to light a point
+    pub samples_count: u32,
+}
+
+impl Light for SphereLight {
+    fn illuminate(&self, point: &Vector, rng: &mut dyn RngCore) -> Option<LightSample> {
+        let axis = *point - self.origin;
+        if axis.square_norm() <= self.radius.powi(2) {
+            return None;
+        }
+        // a random point of the hemisphere facing the lit point
+        let mut normal = random_unit_vector(rng);
+        if normal.dot(&axis) < 0. {
+            normal *= -1.;
+        }
+        let (direction, distance) = towards(point, &(self.origin + normal * self.radius));
+        let cos = -direction.dot(&normal);
+        if cos <= 0. {
+            return None;
+        }
+        // the cosine is 1/2 on average over the hemisphere
+        Some(LightSample {
+            direction,
+            distance,
+            intensity: attenuate(self.intensity, distance, 2. * cos),
+        })
+    }
+
+    fn samples_count(&self) -> u32 {
+        self.samples_count
+    }
+
+    fn get_intersection(&self, ray: &Ray) -> Option<(f32, Color)> {
+        let offset = ray.origin - self.origin;
+        let b = ray.direction.dot(&offset);
+        let delta = b * b - offset.square_norm() + self.radius.powi(2);
+        if delta <= 0. {
+            return None;
+        }
+        let d = [-b - delta.sqrt(), -b + delta.sqrt()]
+            .into_iter()
+            .find(|&d| ray.contains(d))?;
+        Some((d, radiance(self.intensity, PI * self.radius.powi(2))))
+    }
+}
+
+/// A rectangular light, spanned by two edges from one of its corners. It only emits from its
+/// front face, whose normal is `edge_1 × edge_2`, and its intensity is the one of a point
+/// light seen from afar in front of it.
+pub struct RectangleLight {
+    /// a corner of the rectangle
+    pub origin: Vector,
+    pub edge_1: Vector,
+    pub edge_2: Vector,
+    pub intensity: Color,
+    /// the number of points of the surface sampled to light a point
+    pub samples_count: u32,
+}
+
+impl Light for RectangleLight {
+    fn illuminate(&self, point: &Vector, rng: &mut dyn RngCore) -> Option<LightSample> {
+        let position = self.origin
+            + self.edge_1 * rng.random_range(0.0..1.)
+            + self.edge_2 * rng.random_range(0.0..1.);
+        let (direction, distance) = towards(point, &position);
+        let normal = self.edge_1.cross(&self.edge_2).normalize();
+        let cos = -direction.dot(&normal);
+        if cos <= 0. {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            intensity: attenuate(self.intensity, distance, cos),
+        })
+    }
+
+    fn samples_count(&self) -> u32 {
+        self.samples_count
+    }
+
+    fn get_intersection(&self, ray: &Ray) -> Option<(f32, Color)> {
+        let n = self.edge_1.cross(&self.edge_2);
+        let denominator = ray.direction.dot(&n);
+        // the back face does not emit
+        if denominator >= 0. {
+            return None;
+        }
+        let d = (self.origin - ray.origin).dot(&n) / denominator;
+        if !ray.contains(d) {
+            return None;
+        }
+        let w = n * (1. / n.square_norm());
+        let p = ray.direction * d + ray.origin - self.origin;
+        let alpha = w.dot(&p.cross(&self.edge_2));
+        let beta = w.dot(&self.edge_1.cross(&p));
+        if !(0. ..=1.).contains(&alpha) || !(0. ..=1.).contains(&beta) {
+            return None;
+        }
+        Some((d, radiance(self.intensity, n.square_norm().sqrt())))
+    }
+}
+
+/// Return the radiance of a light of a given intensity and apparent area, such that the light
+/// it sheds on diffuse surfaces matches its intensity
+fn radiance(intensity: Color, area: f32) -> Color {
+    intensity / (2. * area)
+}
+
+fn random_unit_vector(rng: &mut dyn RngCore) -> Vector {
+    let z: f32 = rng.random_range(-1.0..1.);
+    let phi: f32 = rng.random_range(0.0..2. * PI);
+    let r = (1. - z * z).sqrt();
+    Vector {
+        x: r * phi.cos(),
+        y: r * phi.sin(),
+        z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    /// Return the average intensity received at a point, over many samples
+    fn average_intensity(light: &dyn Light, point: &Vector) -> f32 {
+        let mut rng = StdRng::seed_from_u64(0);
+        let samples_count = 10_000;
+        let total: f32 = (0..samples_count)
+            .filter_map(|_| light.illuminate(point, &mut rng))
+            .map(|sample| sample.intensity.r)
+            .sum();
+        total / samples_count as f32
+    }
+
+    #[test]
+    fn test_sphere_light() {
+        let light = SphereLight {
+            origin: vector(0., 0., 0.),
+            radius: 1.,
+            intensity: Color::gray(2. * PI),
+            samples_count: 1,
+        };
+        // from afar, the light is a point light
+        let far = average_intensity(&light, &vector(0., 0., 100.)) * 100f32.powi(2);
+        assert!((far - 1.).abs() < 0.05, "{far}");
+        assert!(light
+            .illuminate(&vector(0., 0.5, 0.), &mut rand::rng())
+            .is_none());
+
+        let ray = Ray::new(vector(0., 0., -5.), vector(0., 0., 1.));
+        assert_eq!(light.get_intersection(&ray).map(|(d, _)| d), Some(4.));
+    }
+
+    #[test]
+    fn test_rectangle_light() {
+        let light = RectangleLight {
+            origin: vector(-1., -1., 0.),
+            edge_1: vector(2., 0., 0.),
+            edge_2: vector(0., 2., 0.),
+            intensity: Color::gray(2. * PI),
+            samples_count: 1,
+        };
+        let far = average_intensity(&light, &vector(0., 0., 100.)) * 100f32.powi(2);
+        assert!((far - 1.).abs() < 0.05, "{far}");
+        assert!(light
+            .illuminate(&vector(0., 0., -1.), &mut rand::rng())
+            .is_none());
+
+        // only the front face is visible
+        let ray = Ray::new(vector(0.5, 0.5, 5.), vector(0., 0., -1.));
+        let (d, radiance) = light.get_intersection(&ray).unwrap();
+        assert_eq!(d, 5.);
+        assert_eq!(radiance.r, PI / 4.);
+        let back = Ray::new(vector(0.5, 0.5, -5.), vector(0., 0., 1.));
+        assert!(light.get_intersection(&back).is_none());
+    }
+}
@@ -0,0 +1,1650 @@
+//! Loading of scenes from TOML scene files.
+//!
+//! A scene file describes the camera, the lights, a set of named materials and the shapes
+//! referencing them, both grouped by kind. It may also provide the settings to render it with, see
+//! [`RenderSettings`]:
+//!
+//! ```toml
+//! [settings]
+//! image_size = [640, 480]
+//!
+//! [camera]
+//! point = [0, 0, 55]
+//! target = [0, 0, 0]       # the point the camera looks at
+//! up = [0, -1, 0]          # towards the top of the image, need not be orthogonal
+//! fov = 90                 # opening angle across the width of the image, in degrees
+//! fov_axis = "vertical"    # optional, for an angle across the height of the image instead
+//! projection = "fisheye"   # optional, "perspective" (default), "orthographic",
+//!                          # "equirectangular" or "fisheye"
+//!
+//! [camera.lens]            # optional, blurs the points out of focus
+//! aperture = 0.5           # the radius of the lens
+//! focus_distance = 45      # the distance of the plane in focus, along the axis of the camera
+//! blades = 6               # optional, a polygonal opening shaping the blur, defaults to round
+//! rotation = 15            # optional, the rotation of the blades, in degrees
+//!
+//! [[point_lights]]
+//! origin = [-10, -20, 50]
+//! intensity = 1000         # either a number or RGB, decreasing with the square distance
+//!
+//! [[directional_lights]]
+//! direction = [0, 1, 0]    # the direction the light travels in
+//! intensity = [1, 0.9, 0.7]  # 1 lights a white surface facing it in white
+//!
+//! [[spot_lights]]
+//! origin = [0, -20, 0]
+//! direction = [0, 1, 0]    # the axis of the cone
+//! angle = 30               # between the axis and the border of the cone, in degrees
+//! falloff = 20             # optional, the angle where the light starts fading out
+//! intensity = [500, 0, 0]
+//!
+//! [[sphere_lights]]        # area lights cast soft shadows, and are visible
+//! origin = [0, -40, 25]
+//! radius = 5
+//! intensity = 1000         # the intensity of a point light seen from afar
+//! samples_count = 16       # optional, the number of points sampled to light a point
+//!
+//! [[rectangle_lights]]
+//! origin = [-5, -59, 20]   # a corner
+//! edge_1 = [10, 0, 0]
+//! edge_2 = [0, 0, 10]      # only the front face, of normal edge_1 × edge_2, emits
+//! intensity = 1000
+//!
+//! [materials.white]
+//! color = [255, 255, 255]
+//!
+//! [materials.mirror]
+//! color = [255, 255, 255]
+//! specular = true          # defaults to false
+//!
+//! [materials.brushed_steel]
+//! color = [200, 200, 210]
+//! roughness = 0.3          # a glossy coating, defaults to 0.5 when `metallic` is given
+//! metallic = 1             # defaults to 0, as for plastics
+//!
+//! [materials.glass]
+//! color = [255, 255, 255]
+//! refractive_index = 2.3   # defaults to 0, meaning opaque
+//!
+//! [materials.green_glass_under_water]
+//! color = [255, 255, 255]
+//! refractive_index = 1.5
+//! outer_refractive_index = 1.33  # the medium around the shape, defaults to 1
+//! absorption = [0.2, 0.02, 0.2]  # absorbed per unit of distance inside, defaults to 0
+//!
+//! [materials.lamp]
+//! color = [255, 255, 255]
+//! emission = [4, 3.6, 3.2]  # defaults to black, 1 is as bright as a white surface lit by 1
+//!
+//! [materials.tiled_floor]
+//! color = "tiles"          # the color, roughness and metalness may be textures
+//! roughness = "stains"
+//!
+//! [materials.bricks]
+//! color = [180, 80, 60]
+//! normal_map = "bricks"    # a texture of the normals in the frame of the texture coordinates
+//!
+//! [materials.hammered_copper]
+//! color = [240, 150, 110]
+//! metallic = 1
+//! bump_map = "stains"      # a texture of heights, instead of a normal map
+//! bump_scale = 0.05        # optional, the height of white in scene units, defaults to 1
+//!
+//! [textures.tiles]
+//! type = "checker"
+//! even = [255, 255, 255]   # a color, a number or the name of another texture
+//! odd = "wood"
+//! scale = 8                # optional, the number of squares per unit of texture coordinates
+//!
+//! [textures.stains]
+//! type = "noise"           # Perlin noise
+//! low = 0.2                # numbers are fractions of white
+//! high = 0.8
+//! scale = 4                # optional, the number of features per unit
+//! octaves = 4              # optional, the number of layers of finer noise
+//! seed = 0                 # optional, picks the pattern
+//!
+//! [textures.fade]
+//! type = "gradient"
+//! start = [255, 255, 255]
+//! end = [0, 0, 0]
+//! direction = [1, 0]       # optional, over one unit of texture coordinates
+//!
+//! [textures.wood]
+//! type = "image"
+//! path = "wood.png"        # relative to the scene file
+//! wrap = "mirror"          # "repeat" (default), "clamp" or "mirror"
+//!
+//! [textures.bricks]
+//! type = "image"
+//! path = "bricks_normals.png"  # red, green and blue map x, y and z from -1 to 1
+//! color_space = "linear"   # "auto" (default: sRGB, linear for floating point images), "srgb"
+//!                          # or "linear", which normal and height maps need
+//!
+//! [[spheres]]
+//! origin = [0, 0, 25]
+//! radius = 10
+//! material = "white"
+//!
+//! [[hyperboloids]]
+//! origin = [0, 0, -10]
+//! coefficients = [0.5, 1, 0.5]
+//! material = "mirror"
+//!
+//! [[planes]]
+//! origin = [0, 10, 0]  # any point of the plane
+//! normal = [0, -1, 0]  # the normal of the front face
+//! material = "white"
+//!
+//! [[quads]]
+//! origin = [-5, 10, -5]  # a corner
+//! edge_1 = [10, 0, 0]
+//! edge_2 = [0, 0, 10]    # the normal of the front face is edge_1 × edge_2
+//! material = "white"
+//!
+//! [[disks]]
+//! origin = [0, -10, 0]   # the center
+//! normal = [0, 1, 0]
+//! radius = 5
+//! material = "white"
+//!
+//! [[triangles]]
+//! vertices = [[-5, 0, 0], [5, 0, 0], [0, 5, 0]]  # counterclockwise, seen from the front
+//! normals = [[0, 0, 1], [0, 0, 1], [0, 0, 1]]     # optional, for smooth shading
+//! uvs = [[0, 0], [1, 0], [0.5, 1]]                # optional texture coordinates
+//! material = "white"
+//!
+//! [[meshes]]
+//! positions = [[0, 0, 0], [1, 0, 0], [1, 1, 0], [0, 1, 0]]
+//! triangles = [[0, 1, 2], [0, 2, 3]]              # indices in `positions`
+//! # `normals` and `uvs` are optional, and indexed like `positions`
+//! material = "white"
+//!
+//! [[models]]
+//! path = "teapot.obj"  # relative to the scene file
+//! material = "white"   # optional, overrides the materials of the MTL files
+//! ```
+//!
+//! The perspective and the equidistant fisheye, which may span up to 360°, need a `fov`. The
+//! orthographic projection casts parallel rays from a view of `view_width` scene units
+//! instead, and the equirectangular projection covers the whole sphere around the camera, the
+//! longitude along the width of the image and the latitude along its height. A field the
+//! projection does not use, such as the `fov` of an orthographic camera, is an error.
+//!
+//! The camera may instead be given by the orthogonal vectors of its basis, `direction` and
+//! `right` replacing `target`. Their convention is older: `direction` points from the scene
+//! towards the camera and `up` towards the bottom of the image, as in the built-in example
+//! scene, whose camera is the one above.
+//!
+//! Every shape may be moved by an affine transform, which scales it, then rotates it around
+//! the x, y and z axes, then translates it. All the fields are optional:
+//!
+//! ```toml
+//! [[spheres]]
+//! origin = [0, 0, 0]
+//! radius = 1
+//! material = "white"
+//! transform = { scale = [1, 1, 4], rotate = [0, 45, 0], translate = [0, 0, 25] }  # degrees
+//! ```
+//!
+//! The texture coordinates of spheres are their longitude and latitude, and those of
+//! hyperboloids the angle around their axis and the height. Planes use the distances along
+//! two directions of the plane, quads the fractions of their edges and disks the position in
+//! their enclosing square. Triangles and meshes interpolate their `uvs`. The x and y axes of
+//! normal maps follow the directions in which u and v increase.
+//!
+//! Colors and lights are linear: the channels of colors go from 0 to 255 without gamma, and
+//! a white surface receiving a light of intensity 1 is displayed white, before the exposure
+//! and tone mapping of the settings. Image textures are decoded from sRGB by default.
+//!
+//! The models loaded several times with the same material share their meshes, so that a
+//! model can be instanced with different transforms without duplicating it. Only models are
+//! shared: the other shapes, including `[[meshes]]`, are built again for every entry, so large
+//! geometry placed several times is better imported as a model.
+//!
+//! See [`crate::obj`] for the import of OBJ models.
+use crate::camera::{Camera, CameraError, Fov, Lens, Projection};
+use crate::lights::{DirectionalLight, Light, PointLight, RectangleLight, SphereLight, SpotLight};
+use crate::materials::{Material, NormalMap};
+use crate::obj::{load_obj, ObjError};
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use crate::shapes::{
+    Disk, Hyperboloid, MeshError, Plane, Quad, Shape, Sphere, Transformed, Triangle, TriangleMesh,
+};
+use crate::textures::{Bitmap, Checker, ColorSpace, Constant, Gradient, Noise, Texture, WrapMode};
+use crate::utils::color::Color;
+use crate::utils::matrix::Matrix;
+use crate::utils::vector::Vector;
+use serde::Deserialize;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use toml::Spanned;
+
+/// Return the material of the faces of models which do not have one
+fn default_material() -> Material {
+    Material::diffuse(Color::from([0.8, 0.8, 0.8]))
+}
+
+/// An error raised while loading a scene file
+#[derive(Debug)]
+pub enum LoadError {
+    /// The scene file could not be read
+    Io(std::io::Error),
+    /// The scene file is not valid TOML, or does not match the expected layout
+    Parse(toml::de::Error),
+    /// A shape references a material which is not declared in the `materials` table
+    UnknownMaterial {
+        name: String,
+        line: usize,
+        column: usize,
+    },
+    /// A material or a texture references a texture which is not declared in the `textures`
+    /// table
+    UnknownTexture {
+        name: String,
+        line: usize,
+        column: usize,
+    },
+    /// A texture references itself, directly or through other textures
+    CyclicTexture {
+        name: String,
+        line: usize,
+        column: usize,
+    },
+    /// The image of a texture could not be loaded
+    InvalidImage {
+        error: image::ImageError,
+        line: usize,
+        column: usize,
+    },
+    /// A material has both a normal map and a bump map
+    ConflictingNormalMaps { line: usize, column: usize },
+    /// A mesh is inconsistent, for instance a triangle references a missing vertex
+    InvalidMesh {
+        error: MeshError,
+        line: usize,
+        column: usize,
+    },
+    /// An OBJ model could not be imported
+    InvalidModel {
+        error: ObjError,
+        line: usize,
+        column: usize,
+    },
+    /// A transform is not invertible, for instance it scales a shape by 0
+    SingularTransform { line: usize, column: usize },
+    /// The image size of the settings is 0 pixel wide or high
+    EmptyImage { line: usize, column: usize },
+    /// The camera has neither a target nor a direction and a right vector, or both
+    InvalidCameraOrientation { line: usize, column: usize },
+    /// The projection of the camera needs a field which is missing, such as the `fov` of a
+    /// perspective
+    MissingCameraField {
+        field: &'static str,
+        line: usize,
+        column: usize,
+    },
+    /// The camera has a field which its projection does not use, such as the `fov` of an
+    /// orthographic projection
+    UnusedCameraField {
+        field: &'static str,
+        line: usize,
+        column: usize,
+    },
+    /// The camera is invalid, for instance its field of view is not between 0 and 180°
+    InvalidCamera {
+        error: CameraError,
+        line: usize,
+        column: usize,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "could not read scene file: {err}"),
+            LoadError::Parse(err) => write!(f, "invalid scene file: {err}"),
+            LoadError::UnknownMaterial { name, line, column } => write!(
+                f,
+                "unknown material `{name}` at line {line}, column {column}"
+            ),
+            LoadError::UnknownTexture { name, line, column } => write!(
+                f,
+                "unknown texture `{name}` at line {line}, column {column}"
+            ),
+            LoadError::CyclicTexture { name, line, column } => write!(
+                f,
+                "texture `{name}` at line {line}, column {column} references itself"
+            ),
+            LoadError::InvalidImage {
+                error,
+                line,
+                column,
+            } => write!(f, "invalid image at line {line}, column {column}: {error}"),
+            LoadError::ConflictingNormalMaps { line, column } => write!(
+                f,
+                "bump map at line {line}, column {column} conflicts with the normal map"
+            ),
+            LoadError::InvalidMesh {
+                error,
+                line,
+                column,
+            } => write!(f, "invalid mesh at line {line}, column {column}: {error}"),
+            LoadError::InvalidModel {
+                error,
+                line,
+                column,
+            } => write!(f, "invalid model at line {line}, column {column}: {error}"),
+            LoadError::SingularTransform { line, column } => write!(
+                f,
+                "transform at line {line}, column {column} is not invertible"
+            ),
+            LoadError::EmptyImage { line, column } => write!(
+                f,
+                "settings at line {line}, column {column} have an empty `image_size`"
+            ),
+            LoadError::InvalidCameraOrientation { line, column } => write!(
+                f,
+                "camera at line {line}, column {column} needs either a `target`, or a \
+                 `direction` and a `right` vector"
+            ),
+            LoadError::MissingCameraField {
+                field,
+                line,
+                column,
+            } => write!(
+                f,
+                "camera at line {line}, column {column} needs a `{field}` for its projection"
+            ),
+            LoadError::UnusedCameraField {
+                field,
+                line,
+                column,
+            } => write!(
+                f,
+                "camera at line {line}, column {column} has a `{field}`, which its projection \
+                 does not use"
+            ),
+            LoadError::InvalidCamera {
+                error,
+                line,
+                column,
+            } => write!(f, "invalid camera at line {line}, column {column}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            LoadError::Parse(err) => Some(err),
+            LoadError::UnknownMaterial { .. }
+            | LoadError::UnknownTexture { .. }
+            | LoadError::CyclicTexture { .. }
+            | LoadError::ConflictingNormalMaps { .. }
+            | LoadError::SingularTransform { .. }
+            | LoadError::EmptyImage { .. }
+            | LoadError::InvalidCameraOrientation { .. }
+            | LoadError::MissingCameraField { .. }
+            | LoadError::UnusedCameraField { .. } => None,
+            LoadError::InvalidImage { error, .. } => Some(error),
+            LoadError::InvalidMesh { error, .. } => Some(error),
+            LoadError::InvalidModel { error, .. } => Some(error),
+            LoadError::InvalidCamera { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(err: std::io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for LoadError {
+    fn from(err: toml::de::Error) -> Self {
+        LoadError::Parse(err)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    settings: Option<Spanned<RenderSettings>>,
+    camera: Spanned<CameraDescription>,
+    #[serde(default)]
+    point_lights: Vec<PointLightDescription>,
+    #[serde(default)]
+    directional_lights: Vec<DirectionalLightDescription>,
+    #[serde(default)]
+    spot_lights: Vec<SpotLightDescription>,
+    #[serde(default)]
+    sphere_lights: Vec<SphereLightDescription>,
+    #[serde(default)]
+    rectangle_lights: Vec<RectangleLightDescription>,
+    #[serde(default)]
+    textures: HashMap<String, Spanned<TextureDescription>>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDescription>,
+    #[serde(default)]
+    spheres: Vec<SphereDescription>,
+    #[serde(default)]
+    hyperboloids: Vec<HyperboloidDescription>,
+    #[serde(default)]
+    planes: Vec<PlaneDescription>,
+    #[serde(default)]
+    quads: Vec<QuadDescription>,
+    #[serde(default)]
+    disks: Vec<DiskDescription>,
+    #[serde(default)]
+    triangles: Vec<TriangleDescription>,
+    #[serde(default)]
+    meshes: Vec<Spanned<MeshDescription>>,
+    #[serde(default)]
+    models: Vec<Spanned<ModelDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    point: Vector,
+    /// the point the camera looks at, instead of `direction` and `right`
+    target: Option<Vector>,
+    up: Vector,
+    #[serde(default)]
+    projection: ProjectionKind,
+    /// field of vision of the perspective and fisheye projections, in degrees
+    fov: Option<f32>,
+    fov_axis: Option<FovAxis>,
+    /// the width of the view of the orthographic projection
+    view_width: Option<f32>,
+    /// the direction from the scene towards the camera, `up` then pointing towards the
+    /// bottom of the image
+    direction: Option<Vector>,
+    right: Option<Vector>,
+    lens: Option<LensDescription>,
+}
+
+/// The projections of the camera, see [`Projection`]
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum ProjectionKind {
+    #[default]
+    Perspective,
+    Orthographic,
+    Equirectangular,
+    Fisheye,
+}
+
+/// The dimension of the image spanned by the field of view of the camera
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum FovAxis {
+    #[default]
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LensDescription {
+    aperture: f32,
+    focus_distance: f32,
+    #[serde(default)]
+    blades: u32,
+    /// in degrees
+    #[serde(default)]
+    rotation: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PointLightDescription {
+    origin: Vector,
+    intensity: IntensityDescription,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DirectionalLightDescription {
+    direction: Vector,
+    intensity: IntensityDescription,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpotLightDescription {
+    origin: Vector,
+    direction: Vector,
+    /// in degrees
+    angle: f32,
+    /// in degrees, defaults to `angle`
+    falloff: Option<f32>,
+    intensity: IntensityDescription,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereLightDescription {
+    origin: Vector,
+    radius: f32,
+    intensity: IntensityDescription,
+    #[serde(default = "default_light_samples_count")]
+    samples_count: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RectangleLightDescription {
+    origin: Vector,
+    edge_1: Vector,
+    edge_2: Vector,
+    intensity: IntensityDescription,
+    #[serde(default = "default_light_samples_count")]
+    samples_count: u32,
+}
+
+fn default_light_samples_count() -> u32 {
+    16
+}
+
+/// The intensity of a light, either white or RGB
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IntensityDescription {
+    White(f32),
+    Rgb([f32; 3]),
+}
+
+impl IntensityDescription {
+    fn rgb(&self) -> Color {
+        match *self {
+            IntensityDescription::White(intensity) => Color::gray(intensity),
+            IntensityDescription::Rgb(intensity) => Color::from(intensity),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialDescription {
+    color: Spanned<ValueDescription>,
+    #[serde(default)]
+    specular: bool,
+    roughness: Option<Spanned<ValueDescription>>,
+    metallic: Option<Spanned<ValueDescription>>,
+    normal_map: Option<Spanned<ValueDescription>>,
+    bump_map: Option<Spanned<ValueDescription>>,
+    #[serde(default = "default_bump_scale")]
+    bump_scale: f32,
+    #[serde(default)]
+    refractive_index: f32,
+    #[serde(default)]
+    emission: Color,
+    #[serde(default = "default_outer_refractive_index")]
+    outer_refractive_index: f32,
+    #[serde(default)]
+    absorption: Color,
+}
+
+fn default_outer_refractive_index() -> f32 {
+    1.
+}
+
+fn default_bump_scale() -> f32 {
+    1.
+}
+
+/// A parameter of a material or a texture, either uniform or given by a texture
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ValueDescription {
+    /// a scalar parameter, or a gray level as a fraction of white
+    Number(f32),
+    /// a linear color, whose channels go from 0 to 255
+    Rgb([f32; 3]),
+    /// the name of a texture of the `textures` table
+    Texture(String),
+}
+
+/// A texture, whose kind is given by its `type` field. Its parameters are not spanned, as
+/// the fields of internally tagged enums are buffered before being deserialized.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDescription {
+    Checker {
+        even: ValueDescription,
+        odd: ValueDescription,
+        #[serde(default = "default_texture_scale")]
+        scale: f32,
+    },
+    Gradient {
+        start: ValueDescription,
+        end: ValueDescription,
+        #[serde(default = "default_gradient_direction")]
+        direction: [f32; 2],
+    },
+    Noise {
+        low: ValueDescription,
+        high: ValueDescription,
+        #[serde(default = "default_texture_scale")]
+        scale: f32,
+        #[serde(default = "default_noise_octaves")]
+        octaves: u32,
+        #[serde(default)]
+        seed: u64,
+    },
+    Image {
+        path: PathBuf,
+        #[serde(default)]
+        wrap: WrapMode,
+        #[serde(default)]
+        color_space: ColorSpace,
+    },
+}
+
+fn default_texture_scale() -> f32 {
+    1.
+}
+
+fn default_gradient_direction() -> [f32; 2] {
+    [1., 0.]
+}
+
+fn default_noise_octaves() -> u32 {
+    4
+}
+
+/// The textures of a scene file, built when they are first referenced as they may be
+/// composed of each other
+struct Textures<'a> {
+    descriptions: HashMap<String, Spanned<TextureDescription>>,
+    built: HashMap<String, Arc<dyn Texture>>,
+    /// the textures being built, to detect the cycles
+    pending: HashSet<String>,
+    source: &'a str,
+    directory: &'a Path,
+}
+
+impl Textures<'_> {
+    /// Return the texture of a parameter, `span` locating it for the errors
+    fn value(
+        &mut self,
+        value: ValueDescription,
+        span: Range<usize>,
+    ) -> Result<Arc<dyn Texture>, LoadError> {
+        match value {
+            ValueDescription::Number(value) => Ok(Arc::new(Constant(Color::gray(value)))),
+            ValueDescription::Rgb(color) => Ok(Arc::new(Constant(Color::from(color) / 255.))),
+            ValueDescription::Texture(name) => self.get(name, span),
+        }
+    }
+
+    /// Return a texture by name, building it if needed
+    fn get(&mut self, name: String, span: Range<usize>) -> Result<Arc<dyn Texture>, LoadError> {
+        if let Some(texture) = self.built.get(&name) {
+            return Ok(Arc::clone(texture));
+        }
+        let (line, column) = line_and_column(self.source, span.start);
+        let Some(description) = self.descriptions.remove(&name) else {
+            return Err(if self.pending.contains(&name) {
+                LoadError::CyclicTexture { name, line, column }
+            } else {
+                LoadError::UnknownTexture { name, line, column }
+            });
+        };
+        self.pending.insert(name.clone());
+        let span = description.span();
+        let texture: Arc<dyn Texture> = match description.into_inner() {
+            TextureDescription::Checker { even, odd, scale } => Arc::new(Checker {
+                even: self.value(even, span.clone())?,
+                odd: self.value(odd, span)?,
+                scale,
+            }),
+            TextureDescription::Gradient {
+                start,
+                end,
+                direction,
+            } => Arc::new(Gradient {
+                start: self.value(start, span.clone())?,
+                end: self.value(end, span)?,
+                direction,
+            }),
+            TextureDescription::Noise {
+                low,
+                high,
+                scale,
+                octaves,
+                seed,
+            } => Arc::new(Noise::new(
+                self.value(low, span.clone())?,
+                self.value(high, span)?,
+                scale,
+                octaves,
+                seed,
+            )),
+            TextureDescription::Image {
+                path,
+                wrap,
+                color_space,
+            } => Arc::new(
+                Bitmap::open(self.directory.join(path), wrap, color_space).map_err(|error| {
+                    let (line, column) = line_and_column(self.source, span.start);
+                    LoadError::InvalidImage {
+                        error,
+                        line,
+                        column,
+                    }
+                })?,
+            ),
+        };
+        self.pending.remove(&name);
+        self.built.insert(name, Arc::clone(&texture));
+        Ok(texture)
+    }
+
+    /// Return the texture of a spanned parameter
+    fn spanned(&mut self, value: Spanned<ValueDescription>) -> Result<Arc<dyn Texture>, LoadError> {
+        let span = value.span();
+        self.value(value.into_inner(), span)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SphereDescription {
+    origin: Vector,
+    radius: f32,
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HyperboloidDescription {
+    origin: Vector,
+    coefficients: (f32, f32, f32),
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlaneDescription {
+    origin: Vector,
+    normal: Vector,
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QuadDescription {
+    origin: Vector,
+    edge_1: Vector,
+    edge_2: Vector,
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiskDescription {
+    origin: Vector,
+    normal: Vector,
+    radius: f32,
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TriangleDescription {
+    vertices: [Vector; 3],
+    normals: Option<[Vector; 3]>,
+    uvs: Option<[[f32; 2]; 3]>,
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MeshDescription {
+    positions: Vec<Vector>,
+    normals: Option<Vec<Vector>>,
+    uvs: Option<Vec<[f32; 2]>>,
+    triangles: Vec<[usize; 3]>,
+    material: Spanned<String>,
+    transform: Option<Spanned<TransformDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ModelDescription {
+    path: PathBuf,
+    material: Option<Spanned<String>>,
+    transform: Option<Spanned<TransformDescription>>,
+}
+
+/// The path of a model and the name of the material overriding its own
+type ModelKey = (PathBuf, Option<String>);
+
+/// An affine transform, applying the scale, then the rotations, then the translation
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDescription {
+    scale: Option<Vector>,
+    /// the angles of the rotations around the x, y and z axes, in degrees, applied in this
+    /// order
+    rotate: Option<Vector>,
+    translate: Option<Vector>,
+}
+
+impl TransformDescription {
+    fn matrix(&self) -> Matrix {
+        let scale = self.scale.map_or(Matrix::IDENTITY, Matrix::scaling);
+        let rotation = self.rotate.map_or(Matrix::IDENTITY, |angles| {
+            Matrix::rotation_z(angles.z * PI / 180.)
+                * Matrix::rotation_y(angles.y * PI / 180.)
+                * Matrix::rotation_x(angles.x * PI / 180.)
+        });
+        let translation = self.translate.map_or(Matrix::IDENTITY, Matrix::translation);
+        translation * rotation * scale
+    }
+}
+
+/// Load a scene from a TOML scene file, along with the warnings of its models, such as their
+/// material libraries which could not be read
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<(Scene, Vec<ObjError>), LoadError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)?;
+    parse_scene_in(&source, path.parent().unwrap_or(Path::new("")))
+}
+
+/// Parse a scene from the content of a TOML scene file, along with the warnings of its
+/// models. The paths of the models are relative to the current directory.
+pub fn parse_scene(source: &str) -> Result<(Scene, Vec<ObjError>), LoadError> {
+    parse_scene_in(source, Path::new(""))
+}
+
+/// Parse a scene from the content of a TOML scene file, whose models are relative to
+/// `directory`, along with the warnings of its models
+fn parse_scene_in(source: &str, directory: &Path) -> Result<(Scene, Vec<ObjError>), LoadError> {
+    let file: SceneFile = toml::from_str(source)?;
+    let settings = match file.settings {
+        Some(settings) => {
+            let (width, height) = settings.get_ref().image_size;
+            if width == 0 || height == 0 {
+                let (line, column) = line_and_column(source, settings.span().start);
+                return Err(LoadError::EmptyImage { line, column });
+            }
+            settings.into_inner()
+        }
+        None => RenderSettings::default(),
+    };
+
+    let mut textures = Textures {
+        descriptions: file.textures,
+        built: HashMap::new(),
+        pending: HashSet::new(),
+        source,
+        directory,
+    };
+    let mut materials: HashMap<String, Material> = HashMap::new();
+    for (name, description) in file.materials {
+        let material = Material {
+            color: textures.spanned(description.color)?,
+            specular: description.specular,
+            roughness: description
+                .roughness
+                .map(|roughness| textures.spanned(roughness))
+                .transpose()?,
+            metallic: description
+                .metallic
+                .map(|metallic| textures.spanned(metallic))
+                .transpose()?,
+            normal_map: match (description.normal_map, description.bump_map) {
+                (Some(_), Some(bump_map)) => {
+                    let (line, column) = line_and_column(source, bump_map.span().start);
+                    return Err(LoadError::ConflictingNormalMaps { line, column });
+                }
+                (Some(normal_map), None) => Some(NormalMap::Tangent(textures.spanned(normal_map)?)),
+                (None, Some(bump_map)) => Some(NormalMap::Bump {
+                    height: textures.spanned(bump_map)?,
+                    scale: description.bump_scale,
+                }),
+                (None, None) => None,
+            },
+            refractive_index: description.refractive_index,
+            emission: description.emission,
+            outer_refractive_index: description.outer_refractive_index,
+            absorption: description.absorption,
+        };
+        materials.insert(name, material);
+    }
+
+    let find_material = |name: Spanned<String>| match materials.get(name.get_ref()) {
+        Some(material) => Ok(material.clone()),
+        None => {
+            let (line, column) = line_and_column(source, name.span().start);
+            Err(LoadError::UnknownMaterial {
+                name: name.into_inner(),
+                line,
+                column,
+            })
+        }
+    };
+
+    let mut shapes: Vec<Box<dyn Shape>> = Vec::new();
+    for sphere in file.spheres {
+        let shape = Sphere {
+            origin: sphere.origin,
+            radius: sphere.radius,
+            material: find_material(sphere.material)?,
+        };
+        shapes.push(place(shape, sphere.transform, source)?);
+    }
+    for hyperboloid in file.hyperboloids {
+        let shape = Hyperboloid {
+            origin: hyperboloid.origin,
+            coefficients: hyperboloid.coefficients,
+            material: find_material(hyperboloid.material)?,
+        };
+        shapes.push(place(shape, hyperboloid.transform, source)?);
+    }
+    for plane in file.planes {
+        let shape = Plane {
+            origin: plane.origin,
+            normal: plane.normal.normalize(),
+            material: find_material(plane.material)?,
+        };
+        shapes.push(place(shape, plane.transform, source)?);
+    }
+    for quad in file.quads {
+        let shape = Quad {
+            origin: quad.origin,
+            edge_1: quad.edge_1,
+            edge_2: quad.edge_2,
+            material: find_material(quad.material)?,
+        };
+        shapes.push(place(shape, quad.transform, source)?);
+    }
+    for disk in file.disks {
+        let shape = Disk {
+            origin: disk.origin,
+            normal: disk.normal.normalize(),
+            radius: disk.radius,
+            material: find_material(disk.material)?,
+        };
+        shapes.push(place(shape, disk.transform, source)?);
+    }
+    for triangle in file.triangles {
+        let shape = Triangle {
+            vertices: triangle.vertices,
+            normals: triangle
+                .normals
+                .map(|normals| normals.map(|n| n.normalize())),
+            uvs: triangle.uvs,
+            material: find_material(triangle.material)?,
+        };
+        shapes.push(place(shape, triangle.transform, source)?);
+    }
+    for mesh in file.meshes {
+        let span = mesh.span();
+        let mesh = mesh.into_inner();
+        let material = find_material(mesh.material)?;
+        let normals = mesh
+            .normals
+            .map(|normals| normals.iter().map(Vector::normalize).collect());
+        let shape = TriangleMesh::new(mesh.positions, normals, mesh.uvs, mesh.triangles, material)
+            .map_err(|error| {
+                let (line, column) = line_and_column(source, span.start);
+                LoadError::InvalidMesh {
+                    error,
+                    line,
+                    column,
+                }
+            })?;
+        shapes.push(place(shape, mesh.transform, source)?);
+    }
+    // the models loaded several times with the same material are instances of the same meshes
+    let mut models: HashMap<ModelKey, Vec<Arc<dyn Shape>>> = HashMap::new();
+    let mut warnings = Vec::new();
+    for model in file.models {
+        let span = model.span();
+        let model = model.into_inner();
+        let key = (
+            model.path.clone(),
+            model.material.as_ref().map(|name| name.get_ref().clone()),
+        );
+        let meshes = match models.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let material = model.material.map(find_material).transpose()?;
+                let (groups, model_warnings) =
+                    load_obj(directory.join(&model.path), material, default_material()).map_err(
+                        |error| {
+                            let (line, column) = line_and_column(source, span.start);
+                            LoadError::InvalidModel {
+                                error,
+                                line,
+                                column,
+                            }
+                        },
+                    )?;
+                warnings.extend(model_warnings);
+                entry.insert(
+                    groups
+                        .into_iter()
+                        .map(|group| Arc::new(group.mesh) as Arc<dyn Shape>)
+                        .collect(),
+                )
+            }
+        };
+        for mesh in meshes {
+            let transform = match &model.transform {
+                Some(transform) => Spanned::new(transform.span(), transform.get_ref().matrix()),
+                None => Spanned::new(span.clone(), Matrix::IDENTITY),
+            };
+            shapes.push(instance(Arc::clone(mesh), transform, source)?);
+        }
+    }
+
+    let mut lights: Vec<Box<dyn Light>> = Vec::new();
+    for light in file.point_lights {
+        lights.push(Box::new(PointLight {
+            origin: light.origin,
+            intensity: light.intensity.rgb(),
+        }));
+    }
+    for light in file.directional_lights {
+        lights.push(Box::new(DirectionalLight {
+            direction: light.direction.normalize(),
+            intensity: light.intensity.rgb(),
+        }));
+    }
+    for light in file.spot_lights {
+        let angle = light.angle * PI / 180.;
+        lights.push(Box::new(SpotLight {
+            origin: light.origin,
+            direction: light.direction.normalize(),
+            angle,
+            falloff: light.falloff.map_or(angle, |falloff| falloff * PI / 180.),
+            intensity: light.intensity.rgb(),
+        }));
+    }
+    for light in file.sphere_lights {
+        lights.push(Box::new(SphereLight {
+            origin: light.origin,
+            radius: light.radius,
+            intensity: light.intensity.rgb(),
+            samples_count: light.samples_count,
+        }));
+    }
+    for light in file.rectangle_lights {
+        lights.push(Box::new(RectangleLight {
+            origin: light.origin,
+            edge_1: light.edge_1,
+            edge_2: light.edge_2,
+            intensity: light.intensity.rgb(),
+            samples_count: light.samples_count,
+        }));
+    }
+
+    let camera = camera(file.camera, source)?;
+    Ok((Scene::new(shapes, lights, camera, settings), warnings))
+}
+
+/// Build the camera of a scene file
+fn camera(description: Spanned<CameraDescription>, source: &str) -> Result<Camera, LoadError> {
+    let (line, column) = line_and_column(source, description.span().start);
+    let description = description.into_inner();
+    let missing = |field| LoadError::MissingCameraField {
+        field,
+        line,
+        column,
+    };
+    // a field the projection ignores is most likely a mistake, rather than left on purpose
+    let unused = match description.projection {
+        ProjectionKind::Perspective | ProjectionKind::Fisheye => {
+            description.view_width.map(|_| "view_width")
+        }
+        ProjectionKind::Orthographic | ProjectionKind::Equirectangular => description
+            .fov
+            .map(|_| "fov")
+            .or(description.fov_axis.as_ref().map(|_| "fov_axis")),
+    };
+    if let Some(field) = unused {
+        return Err(LoadError::UnusedCameraField {
+            field,
+            line,
+            column,
+        });
+    }
+    let fov = description.fov.map(|fov| {
+        let angle = fov * PI / 180.;
+        match description.fov_axis.unwrap_or_default() {
+            FovAxis::Horizontal => Fov::Horizontal(angle),
+            FovAxis::Vertical => Fov::Vertical(angle),
+        }
+    });
+    let projection = match description.projection {
+        ProjectionKind::Perspective => Projection::Perspective {
+            fov: fov.ok_or_else(|| missing("fov"))?,
+        },
+        ProjectionKind::Orthographic => Projection::Orthographic {
+            width: description
+                .view_width
+                .ok_or_else(|| missing("view_width"))?,
+        },
+        ProjectionKind::Equirectangular => Projection::Equirectangular,
+        ProjectionKind::Fisheye => Projection::Fisheye {
+            fov: fov.ok_or_else(|| missing("fov"))?,
+        },
+    };
+    let camera = match (description.target, description.direction, description.right) {
+        (Some(target), None, None) => {
+            Camera::look_at_with(description.point, target, description.up, projection)
+        }
+        (None, Some(direction), Some(right)) => Camera::new(
+            description.point,
+            direction * -1.,
+            description.up * -1.,
+            right,
+            projection,
+        ),
+        _ => return Err(LoadError::InvalidCameraOrientation { line, column }),
+    };
+    let invalid = |error| LoadError::InvalidCamera {
+        error,
+        line,
+        column,
+    };
+    let mut camera = camera.map_err(invalid)?;
+    camera.lens = description
+        .lens
+        .map(|lens| {
+            let rotation = lens.rotation * PI / 180.;
+            Lens::new(lens.aperture, lens.focus_distance, lens.blades, rotation)
+        })
+        .transpose()
+        .map_err(invalid)?;
+    Ok(camera)
+}
+
+/// Move a shape by its transform, if it has one. The shape belongs to this entry of the scene
+/// file only, unlike the meshes of models, which are shared by their instances.
+fn place<S: Shape + 'static>(
+    shape: S,
+    transform: Option<Spanned<TransformDescription>>,
+    source: &str,
+) -> Result<Box<dyn Shape>, LoadError> {
+    match transform {
+        Some(transform) => {
+            let matrix = Spanned::new(transform.span(), transform.get_ref().matrix());
+            instance(Arc::new(shape), matrix, source)
+        }
+        None => Ok(Box::new(shape)),
+    }
+}
+
+/// Return an instance of a shared shape, moved by a transform
+fn instance(
+    shape: Arc<dyn Shape>,
+    transform: Spanned<Matrix>,
+    source: &str,
+) -> Result<Box<dyn Shape>, LoadError> {
+    match Transformed::new(shape, *transform.get_ref()) {
+        Some(shape) => Ok(Box::new(shape)),
+        None => {
+            let (line, column) = line_and_column(source, transform.span().start);
+            Err(LoadError::SingularTransform { line, column })
+        }
+    }
+}
+
+/// Convert a byte offset into 1-based line and column numbers
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::IntegratorKind;
+
+    /// Parse a scene, without the warnings of its models
+    fn parse(source: &str) -> Result<Scene, LoadError> {
+        parse_scene(source).map(|(scene, _)| scene)
+    }
+
+    const SCENE: &str = r#"
+[camera]
+point = [0, 0, 55]
+fov = 90
+direction = [0, 0, 1]
+up = [0, 1, 0]
+right = [1, 0, 0]
+
+[[point_lights]]
+origin = [-10, -20, 50]
+intensity = 1000
+
+[materials.white]
+color = [255, 255, 255]
+
+[[spheres]]
+origin = [0, 0, 25]
+radius = 10
+material = "white"
+"#;
+
+    #[test]
+    fn test_parse_scene() {
+        let scene = parse(SCENE).unwrap();
+        assert_eq!(scene.shapes().len(), 1);
+        assert_eq!(scene.lights.len(), 1);
+        assert_eq!(
+            scene.camera.projection,
+            Projection::Perspective {
+                fov: Fov::Horizontal(PI / 2.)
+            }
+        );
+        assert_eq!(scene.settings, RenderSettings::default());
+    }
+
+    #[test]
+    fn test_parse_example_scene() {
+        let scene = parse(crate::EXAMPLE_SCENE).unwrap();
+        assert_eq!(scene.shapes().len(), 7);
+    }
+
+    #[test]
+    fn test_unknown_material() {
+        let source = SCENE.replace("material = \"white\"", "material = \"black\"");
+        match parse(&source) {
+            Err(LoadError::UnknownMaterial { name, line, .. }) => {
+                assert_eq!(name, "black");
+                assert_eq!(line, 19);
+            }
+            _ => panic!("expected an unknown material error"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_field() {
+        let source = SCENE.replace("radius = 10", "radius = \"big\"");
+        let err = parse(&source).err().expect("expected a parse error");
+        assert!(matches!(err, LoadError::Parse(_)));
+        assert!(err.to_string().contains("line 18"));
+    }
+
+    #[test]
+    fn test_empty_image() {
+        let settings = "[settings]\nimage_size = [640, 480]\n";
+        let scene = parse(&format!("{settings}{SCENE}")).unwrap();
+        assert_eq!(scene.settings.image_size, (640, 480));
+        for image_size in ["[0, 0]", "[640, 0]", "[0, 480]"] {
+            let source = format!("{}{SCENE}", settings.replace("[640, 480]", image_size));
+            assert!(matches!(
+                parse(&source),
+                Err(LoadError::EmptyImage { line: 1, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_invalid_mesh() {
+        let source = format!(
+            "{SCENE}
+[[meshes]]
+positions = [[0, 0, 0], [1, 0, 0], [1, 1, 0]]
+triangles = [[0, 1, 3]]
+material = \"white\"
+"
+        );
+        match parse(&source) {
+            Err(LoadError::InvalidMesh { line, .. }) => assert_eq!(line, 21),
+            _ => panic!("expected an invalid mesh error"),
+        }
+    }
+
+    #[test]
+    fn test_lights() {
+        let source = format!(
+            "{SCENE}
+[[directional_lights]]
+direction = [0, 2, 0]
+intensity = [1, 0.5, 0]
+
+[[spot_lights]]
+origin = [0, 0, 0]
+direction = [0, 0, 1]
+angle = 30
+intensity = 1000
+
+[[sphere_lights]]
+origin = [0, -40, 25]
+radius = 5
+intensity = 1000
+"
+        );
+        let scene = parse(&source).unwrap();
+        assert_eq!(scene.lights.len(), 4);
+        assert_eq!(scene.lights[3].samples_count(), 16);
+        let sun = scene.lights[1]
+            .illuminate(
+                &Vector {
+                    x: 0.,
+                    y: 0.,
+                    z: 0.,
+                },
+                &mut rand::rng(),
+            )
+            .unwrap();
+        assert_eq!(sun.direction.y, -1.);
+        assert_eq!(sun.intensity, Color::from([1., 0.5, 0.]));
+    }
+
+    #[test]
+    fn test_lens() {
+        assert!(parse(SCENE).unwrap().camera.lens.is_none());
+        let source = SCENE.replace(
+            "right = [1, 0, 0]",
+            "right = [1, 0, 0]
+lens = { aperture = 0.5, focus_distance = 30, blades = 6, rotation = 90 }",
+        );
+        let lens = parse(&source).unwrap().camera.lens.unwrap();
+        assert_eq!(lens.focus_distance, 30.);
+        assert_eq!(lens.blades, 6);
+        assert_eq!(lens.rotation, PI / 2.);
+        let source = SCENE.replace(
+            "right = [1, 0, 0]",
+            "right = [1, 0, 0]\nlens = { aperture = 0.5 }",
+        );
+        assert!(matches!(parse(&source), Err(LoadError::Parse(_))));
+        let source = SCENE.replace(
+            "right = [1, 0, 0]",
+            "right = [1, 0, 0]\nlens = { aperture = 0.5, focus_distance = 0 }",
+        );
+        assert!(matches!(
+            parse(&source),
+            Err(LoadError::InvalidCamera {
+                error: CameraError::InvalidLens { .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_look_at_camera() {
+        let source = SCENE.replace(
+            "direction = [0, 0, 1]\nup = [0, 1, 0]\nright = [1, 0, 0]",
+            "target = [0, 0, 25]\nup = [0, -1, 0]\nfov_axis = \"vertical\"",
+        );
+        let camera = parse(&source).unwrap().camera;
+        assert_eq!(camera.direction.z, -1.);
+        assert_eq!(camera.up.y, -1.);
+        assert_eq!(camera.right.x, 1.);
+        assert_eq!(
+            camera.projection,
+            Projection::Perspective {
+                fov: Fov::Vertical(PI / 2.)
+            }
+        );
+
+        // both conventions frame the scene the same way, rather than mirrored
+        let legacy = parse(SCENE).unwrap().camera;
+        assert_eq!(legacy.direction, camera.direction);
+        assert_eq!(legacy.up, camera.up);
+        assert_eq!(legacy.right, camera.right);
+    }
+
+    #[test]
+    fn test_vertical_fov_follows_image_size() {
+        let source = SCENE.replace("fov = 90", "fov = 90\nfov_axis = \"vertical\"");
+        let scene = parse(&source).unwrap();
+        // a wider image than the one of the scene file
+        let settings = RenderSettings {
+            image_size: (400, 100),
+            integrator: IntegratorKind::Normals,
+            seed: Some(0),
+            progress: false,
+            ..scene.settings.clone()
+        };
+        let image = scene.render(&settings).unwrap();
+        // the sphere, 30 units away with a radius of 10, spans tan(asin(1 / 3)) ≈ 0.35 of the
+        // height of the 90° view, rather than the whole column
+        let rows = (0..100)
+            .filter(|&y| image.get_pixel(200, y) != Color::BLACK)
+            .count();
+        assert!((33..=38).contains(&rows), "{rows} rows");
+    }
+
+    #[test]
+    fn test_model_warnings() {
+        let directory =
+            std::env::temp_dir().join(format!("ray-tracer-loader-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let obj = "mtllib missing.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3\n";
+        std::fs::write(directory.join("triangle.obj"), obj).unwrap();
+        let model = "\n[[models]]\npath = \"triangle.obj\"\n";
+        std::fs::write(
+            directory.join("scene.toml"),
+            format!("{SCENE}{model}{model}"),
+        )
+        .unwrap();
+        // the model is loaded once for both of its instances, so it warns once
+        let (scene, warnings) = load_scene(directory.join("scene.toml")).unwrap();
+        assert_eq!(scene.shapes().len(), 3);
+        assert!(matches!(
+            &warnings[..],
+            [ObjError::Io { path, .. }] if path.ends_with("missing.mtl")
+        ));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_projections() {
+        let with_projection =
+            |projection: &str| SCENE.replace("fov = 90", &format!("projection = {projection}"));
+        let scene = parse(&with_projection("\"orthographic\"\nview_width = 40")).unwrap();
+        assert_eq!(
+            scene.camera.projection,
+            Projection::Orthographic { width: 40. }
+        );
+        let scene = parse(&with_projection("\"equirectangular\"")).unwrap();
+        assert_eq!(scene.camera.projection, Projection::Equirectangular);
+        let scene = parse(&with_projection("\"fisheye\"\nfov = 360")).unwrap();
+        assert_eq!(
+            scene.camera.projection,
+            Projection::Fisheye {
+                fov: Fov::Horizontal(2. * PI)
+            }
+        );
+        match parse(&with_projection("\"orthographic\"")) {
+            Err(LoadError::MissingCameraField { field, .. }) => assert_eq!(field, "view_width"),
+            _ => panic!("expected a missing camera field error"),
+        }
+        let unused = [
+            ("\"orthographic\"\nview_width = 40\nfov = 90", "fov"),
+            ("\"equirectangular\"\nfov_axis = \"vertical\"", "fov_axis"),
+            ("\"perspective\"\nfov = 90\nview_width = 40", "view_width"),
+            ("\"fisheye\"\nfov = 180\nview_width = 40", "view_width"),
+        ];
+        for (projection, expected) in unused {
+            match parse(&with_projection(projection)) {
+                Err(LoadError::UnusedCameraField { field, line, .. }) => {
+                    assert_eq!((field, line), (expected, 2))
+                }
+                _ => panic!("expected an unused camera field error for {projection}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_camera() {
+        let source = SCENE.replace("direction = [0, 0, 1]\n", "");
+        match parse(&source) {
+            Err(LoadError::InvalidCameraOrientation { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("expected an invalid camera orientation error"),
+        }
+        let source = SCENE.replace("right = [1, 0, 0]", "target = [0, 0, 0]");
+        assert!(matches!(
+            parse(&source),
+            Err(LoadError::InvalidCameraOrientation { .. })
+        ));
+        let source = SCENE.replace("up = [0, 1, 0]", "up = [0, 1, 1]");
+        assert!(matches!(
+            parse(&source),
+            Err(LoadError::InvalidCamera {
+                error: CameraError::NotOrthogonal,
+                ..
+            })
+        ));
+        let source = SCENE.replace("fov = 90", "fov = 180");
+        assert!(matches!(
+            parse(&source),
+            Err(LoadError::InvalidCamera {
+                error: CameraError::InvalidFov(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_transform() {
+        let source = SCENE.replace(
+            "radius = 10",
+            "radius = 1\ntransform = { scale = [10, 10, 20], rotate = [90, 0, 0] }",
+        );
+        let scene = parse(&source).unwrap();
+        let bounds = scene.shapes()[0].bounds();
+        // the sphere is stretched along z, then laid along y
+        let size = bounds.max - bounds.min;
+        assert!((size.y - 40.).abs() < 1e-3);
+        assert!((size.z - 20.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_dielectric_material() {
+        let source = SCENE.replace(
+            "color = [255, 255, 255]",
+            "color = [255, 255, 255]\nrefractive_index = 1.5\nabsorption = [0.5, 0, 0]",
+        );
+        let scene = parse(&source).unwrap();
+        let material = scene.shapes()[0].get_material();
+        assert_eq!(material.outer_refractive_index, 1.);
+        let transmittance = material.transmittance(2.);
+        assert!((transmittance.r - (-1f32).exp()).abs() < 1e-6);
+        assert_eq!(transmittance.g, 1.);
+    }
+
+    #[test]
+    fn test_textures() {
+        let source = format!(
+            "{}
+[textures.tiles]
+type = \"checker\"
+even = [255, 0, 0]
+odd = \"fade\"
+scale = 2
+
+[textures.fade]
+type = \"gradient\"
+start = 0
+end = 1
+",
+            SCENE.replace(
+                "color = [255, 255, 255]",
+                "color = \"tiles\"\nroughness = \"fade\""
+            )
+        );
+        let scene = parse(&source).unwrap();
+        let material = scene.shapes()[0].get_material();
+        assert_eq!(material.color.value([0.1, 0.1]), Color::from([1., 0., 0.]));
+        assert_eq!(material.color.value([0.75, 0.1]), Color::gray(0.75));
+        let roughness = material.shading([0.25, 0.]).microfacet.unwrap().roughness;
+        assert_eq!(roughness, 0.25);
+    }
+
+    #[test]
+    fn test_normal_maps() {
+        let bumpy = SCENE.replace(
+            "color = [255, 255, 255]",
+            "color = [255, 255, 255]\nbump_map = 1\nbump_scale = 0.5",
+        );
+        let scene = parse(&bumpy).unwrap();
+        let material = scene.shapes()[0].get_material();
+        assert!(matches!(
+            material.normal_map,
+            Some(NormalMap::Bump { scale: 0.5, .. })
+        ));
+
+        let conflicting =
+            bumpy.replace("bump_map = 1", "normal_map = [128, 128, 255]\nbump_map = 1");
+        match parse(&conflicting) {
+            Err(LoadError::ConflictingNormalMaps { line, .. }) => assert_eq!(line, 16),
+            _ => panic!("expected conflicting normal maps"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_texture() {
+        let source = SCENE.replace("color = [255, 255, 255]", "color = \"wood\"");
+        match parse(&source) {
+            Err(LoadError::UnknownTexture { name, line, .. }) => {
+                assert_eq!(name, "wood");
+                assert_eq!(line, 14);
+            }
+            _ => panic!("expected an unknown texture error"),
+        }
+    }
+
+    #[test]
+    fn test_cyclic_texture() {
+        let source = format!(
+            "{}
+[textures.loop]
+type = \"checker\"
+even = 0
+odd = \"loop\"
+",
+            SCENE.replace("color = [255, 255, 255]", "color = \"loop\"")
+        );
+        match parse(&source) {
+            Err(LoadError::CyclicTexture { name, line, .. }) => {
+                assert_eq!(name, "loop");
+                assert_eq!(line, 21);
+            }
+            _ => panic!("expected a cyclic texture error"),
+        }
+    }
+
+    #[test]
+    fn test_singular_transform() {
+        let source = SCENE.replace(
+            "radius = 10",
+            "radius = 10\ntransform = { scale = [1, 0, 1] }",
+        );
+        match parse(&source) {
+            Err(LoadError::SingularTransform { line, .. }) => assert_eq!(line, 19),
+            _ => panic!("expected a singular transform error"),
+        }
+    }
+
+    #[test]
+    fn test_line_and_column() {
+        assert_eq!(line_and_column("ab\ncd", 4), (2, 2));
+    }
+}
@@ -1,5 +1,122 @@
-use ray_tracer::make_image;
+use clap::Parser;
+use ray_tracer::aovs::Aov;
+use ray_tracer::example_scene;
+use ray_tracer::framebuffer::ToneMapping;
+use ray_tracer::loader::load_scene;
+use ray_tracer::output::{ExrPrecision, OutputFormat};
+use ray_tracer::scene::RenderError;
+use ray_tracer::settings::{IntegratorKind, RenderSettings};
+use std::path::PathBuf;
+use std::process::ExitCode;
 
-fn main() {
-    make_image();
+/// Render a scene file to an image
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Scene file to render. Renders the built-in example scene when omitted
+    scene: Option<PathBuf>,
+    /// Path of the generated image, whose extension selects the format. `.exr`, `.hdr` and
+    /// `.pfm` images hold the linear colors, without exposure nor tone mapping
+    #[arg(short, long, default_value = "generated.png")]
+    output: PathBuf,
+    /// Width of the image, in pixels
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    width: Option<u32>,
+    /// Height of the image, in pixels
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    height: Option<u32>,
+    /// Number of diffused light samples per pixel
+    #[arg(short, long)]
+    samples: Option<u32>,
+    /// Maximum number of bounces of a ray
+    #[arg(short, long)]
+    bounces: Option<u8>,
+    /// Rendering algorithm: `whitted`, `path`, `ambient_occlusion` or `normals`
+    #[arg(short, long)]
+    integrator: Option<IntegratorKind>,
+    /// Brightness of the image, in stops: each one doubles the light
+    #[arg(short, long, allow_negative_numbers = true)]
+    exposure: Option<f32>,
+    /// Display of the colors brighter than white: `clamp`, `reinhard` or `aces`
+    #[arg(short, long)]
+    tone_mapping: Option<ToneMapping>,
+    /// Precision of the channels of OpenEXR images: `half` or `full`
+    #[arg(long)]
+    exr_precision: Option<ExrPrecision>,
+    /// Passes to render along with the image, separated by commas: `depth`, `position`,
+    /// `normal`, `albedo`, `object_id`, `material_id`, `direct`, `indirect` or `shadow`.
+    /// They are layers of `.exr` images, and separate images named like `image.depth.png`
+    /// otherwise
+    #[arg(short, long, value_delimiter = ',')]
+    aov: Vec<Aov>,
+    /// Seed of the random number generator, for reproducible renders
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Number of render threads, defaults to the number of cores
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u16).range(1..))]
+    threads: Option<u16>,
+    /// Do not print progress
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+impl Args {
+    /// Override the settings of the scene with the command-line arguments
+    fn settings(&self, defaults: &RenderSettings) -> RenderSettings {
+        RenderSettings {
+            image_size: (
+                self.width.unwrap_or(defaults.image_size.0),
+                self.height.unwrap_or(defaults.image_size.1),
+            ),
+            max_bounces: self.bounces.unwrap_or(defaults.max_bounces),
+            diffused_samples_count: self.samples.unwrap_or(defaults.diffused_samples_count),
+            seed: self.seed.or(defaults.seed),
+            exposure: self.exposure.unwrap_or(defaults.exposure),
+            tone_mapping: self.tone_mapping.unwrap_or(defaults.tone_mapping),
+            exr_precision: self.exr_precision.unwrap_or(defaults.exr_precision),
+            integrator: self.integrator.unwrap_or(defaults.integrator),
+            aovs: if self.aov.is_empty() {
+                defaults.aovs.clone()
+            } else {
+                self.aov.clone()
+            },
+            threads: self.threads.map(usize::from).or(defaults.threads),
+            progress: !self.quiet,
+            ..defaults.clone()
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if let Err(err) = OutputFormat::from_path(&args.output) {
+        eprintln!("error: {err}");
+        return ExitCode::FAILURE;
+    }
+    let scene = match &args.scene {
+        Some(path) => match load_scene(path) {
+            Ok((scene, warnings)) => {
+                for warning in warnings {
+                    eprintln!("warning: {}: {warning}", path.display());
+                }
+                scene
+            }
+            Err(err) => {
+                eprintln!("error: {}: {err}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => example_scene(),
+    };
+    match scene.generate_image(&args.settings(&scene.settings), &args.output) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(RenderError::Output(err)) => {
+            eprintln!("error: could not write {}: {err}", args.output.display());
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -1,6 +1,233 @@
-#[derive(Debug, Copy, Clone)]
+use crate::textures::{Constant, Texture};
+use crate::utils::color::Color;
+use crate::utils::vector::Vector;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+mod microfacet;
+mod normal_map;
+
+pub use microfacet::Microfacet;
+pub use normal_map::NormalMap;
+
+/// The reflectance at normal incidence of the dielectrics, such as plastics
+const DIELECTRIC_REFLECTANCE: f32 = 0.04;
+
+#[derive(Debug, Clone)]
 pub struct Material {
-    pub color: image::Rgb<u8>,
+    pub color: Arc<dyn Texture>,
     pub specular: bool,
+    /// the roughness of the glossy coating of a physically based material, which is only
+    /// diffuse without a roughness nor a metalness, see [`Microfacet`]
+    pub roughness: Option<Arc<dyn Texture>>,
+    pub metallic: Option<Arc<dyn Texture>>,
+    /// the relief of the surface, perturbing its shading normals
+    pub normal_map: Option<NormalMap>,
+    /// the refractive index of the inside of a transparent material, which is opaque when 0
     pub refractive_index: f32,
+    /// the refractive index of the medium surrounding the material, such as 1.33 for glass
+    /// under water
+    pub outer_refractive_index: f32,
+    /// the fraction of each channel absorbed per unit of distance travelled inside a
+    /// transparent material, following the Beer–Lambert law
+    pub absorption: Color,
+    /// the light emitted by the surface: an emission of 1 is as bright as a white surface
+    /// receiving a unit of light
+    pub emission: Color,
+}
+
+impl Material {
+    /// Return an opaque and matte material of a uniform color
+    pub fn diffuse(color: Color) -> Self {
+        Material {
+            color: Arc::new(Constant(color)),
+            specular: false,
+            roughness: None,
+            metallic: None,
+            normal_map: None,
+            refractive_index: 0.,
+            outer_refractive_index: 1.,
+            absorption: Color::BLACK,
+            emission: Color::BLACK,
+        }
+    }
+
+    /// Return the fraction of each channel transmitted over a distance inside the material
+    pub fn transmittance(&self, distance: f32) -> Color {
+        self.absorption
+            .map(|absorption| (-absorption * distance).exp())
+    }
+
+    /// Return whether two materials are identical, their textures being compared by identity
+    pub fn is_same(&self, other: &Material) -> bool {
+        fn same_texture(a: &Option<Arc<dyn Texture>>, b: &Option<Arc<dyn Texture>>) -> bool {
+            match (a, b) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+        }
+        let same_normal_map = match (&self.normal_map, &other.normal_map) {
+            (Some(NormalMap::Tangent(a)), Some(NormalMap::Tangent(b))) => Arc::ptr_eq(a, b),
+            (
+                Some(NormalMap::Bump { height, scale }),
+                Some(NormalMap::Bump {
+                    height: other_height,
+                    scale: other_scale,
+                }),
+            ) => Arc::ptr_eq(height, other_height) && scale == other_scale,
+            (None, None) => true,
+            _ => false,
+        };
+        Arc::ptr_eq(&self.color, &other.color)
+            && self.specular == other.specular
+            && same_texture(&self.roughness, &other.roughness)
+            && same_texture(&self.metallic, &other.metallic)
+            && same_normal_map
+            && self.refractive_index == other.refractive_index
+            && self.outer_refractive_index == other.outer_refractive_index
+            && self.absorption == other.absorption
+            && self.emission == other.emission
+    }
+
+    /// Return the properties of the material at a point of a surface, given by its texture
+    /// coordinates
+    pub fn shading(&self, uv: [f32; 2]) -> Shading {
+        Shading {
+            albedo: self.color.value(uv),
+            microfacet: Microfacet::from_parameters(
+                self.roughness
+                    .as_ref()
+                    .map(|roughness| roughness.scalar(uv)),
+                self.metallic.as_ref().map(|metallic| metallic.scalar(uv)),
+            ),
+        }
+    }
+}
+
+/// The properties of a material at a point of a surface, its textures being evaluated
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Shading {
+    /// the color, as fractions of each channel
+    pub albedo: Color,
+    /// the glossy coating, without which the surface is only diffuse
+    pub microfacet: Option<Microfacet>,
+}
+
+impl Shading {
+    /// Return the fraction of each channel diffused by the surface, seen from the direction
+    /// `view`
+    pub fn diffuse(&self, normal: &Vector, view: &Vector) -> Color {
+        let albedo = self.albedo;
+        let Some(microfacet) = self.microfacet else {
+            return albedo;
+        };
+        // the light reflected by the glossy coating does not reach the diffuse layer
+        let fresnel = self.fresnel(normal.dot(view).max(0.));
+        albedo * fresnel.map(|fresnel| (1. - microfacet.metallic) * (1. - fresnel))
+    }
+
+    /// Return the fraction of each channel of the light coming from the direction `light`
+    /// reflected by the glossy coating towards the direction `view`, relative to a white
+    /// diffuse surface
+    pub fn glossy(&self, normal: &Vector, view: &Vector, light: &Vector) -> Color {
+        let Some(microfacet) = self.microfacet else {
+            return Color::BLACK;
+        };
+        let (cos_view, cos_light) = (normal.dot(view), normal.dot(light));
+        if cos_view <= 0. || cos_light <= 0. {
+            return Color::BLACK;
+        }
+        let half = (*view + *light).normalize();
+        let fresnel = self.fresnel(view.dot(&half).max(0.));
+        let factor = PI
+            * microfacet.distribution(normal.dot(&half))
+            * microfacet.shadowing(cos_view, cos_light)
+            / (4. * cos_view * cos_light);
+        fresnel * factor
+    }
+
+    /// Return the fraction of each channel reflected by the glossy coating at an angle of
+    /// cosine `cos`, following Schlick's approximation
+    pub(crate) fn fresnel(&self, cos: f32) -> Color {
+        let metallic = self.microfacet.map_or(0., |microfacet| microfacet.metallic);
+        self.albedo.map(|albedo| {
+            let normal_reflectance =
+                DIELECTRIC_REFLECTANCE + (albedo - DIELECTRIC_REFLECTANCE) * metallic;
+            normal_reflectance + (1. - normal_reflectance) * (1. - cos).powi(5)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::textures::Checker;
+
+    fn shading(metallic: f32) -> Shading {
+        Shading {
+            albedo: Color::from([1., 0.5, 0.]),
+            microfacet: Some(Microfacet {
+                roughness: 0.5,
+                metallic,
+            }),
+        }
+    }
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    #[test]
+    fn test_metal() {
+        let metal = shading(1.);
+        let normal = vector(0., 1., 0.);
+        let view = vector(0.6, 0.8, 0.);
+        assert_eq!(metal.diffuse(&normal, &view), Color::BLACK);
+        // the highlight is in the color of the metal, and brightest in the mirror direction
+        let mirror = metal.glossy(&normal, &view, &vector(-0.6, 0.8, 0.));
+        assert!(mirror.r > mirror.g && mirror.g > mirror.b);
+        let aside = metal.glossy(&normal, &view, &vector(0., 0.8, 0.6));
+        assert!(mirror.r > aside.r);
+        assert_eq!(
+            metal.glossy(&normal, &view, &vector(0., -1., 0.)),
+            Color::BLACK
+        );
+    }
+
+    #[test]
+    fn test_plastic() {
+        let plastic = shading(0.);
+        let normal = vector(0., 1., 0.);
+        let diffuse = plastic.diffuse(&normal, &normal);
+        assert!((diffuse.r - 0.96).abs() < 1e-4);
+        assert_eq!(diffuse.b, 0.);
+        // the highlight is white
+        let highlight = plastic.glossy(&normal, &normal, &normal);
+        assert!((highlight.r - highlight.b).abs() < 1e-6 && highlight.r > 0.);
+
+        let matte = Shading {
+            microfacet: None,
+            ..plastic
+        };
+        assert_eq!(matte.diffuse(&normal, &normal), Color::from([1., 0.5, 0.]));
+        assert_eq!(matte.glossy(&normal, &normal, &normal), Color::BLACK);
+    }
+
+    #[test]
+    fn test_textured_material() {
+        let material = Material {
+            roughness: Some(Arc::new(Checker {
+                even: Arc::new(Constant(Color::gray(0.2))),
+                odd: Arc::new(Constant(Color::gray(0.8))),
+                scale: 1.,
+            })),
+            ..Material::diffuse(Color::gray(0.5))
+        };
+        let roughness = |uv| material.shading(uv).microfacet.unwrap().roughness;
+        assert_eq!(roughness([0.5, 0.5]), 0.2);
+        assert_eq!(roughness([1.5, 0.5]), 0.8);
+        assert_eq!(material.shading([1.5, 0.5]).albedo, Color::gray(0.5));
+        assert_eq!(material.shading([0., 0.]).microfacet.unwrap().metallic, 0.);
+    }
 }
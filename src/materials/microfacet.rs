@@ -0,0 +1,126 @@
+use crate::constants::SMOOTH_ROUGHNESS;
+use crate::utils::vector::Vector;
+use rand::{Rng, RngCore};
+use std::f32::consts::PI;
+
+/// The roughness of the materials which only give their metalness
+const DEFAULT_ROUGHNESS: f32 = 0.5;
+
+/// The glossy reflection of a rough surface, made of tiny mirrors whose orientations follow
+/// the GGX (Trowbridge–Reitz) distribution
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Microfacet {
+    /// from 0 for a polished surface to 1 for a very rough one
+    pub roughness: f32,
+    /// from 0 for a dielectric such as plastic, which reflects white highlights over its
+    /// diffuse color, to 1 for a metal, which reflects in its color only
+    pub metallic: f32,
+}
+
+impl Microfacet {
+    /// Return the glossy coating of a material given its optional roughness and metalness,
+    /// or none when neither is given
+    pub fn from_parameters(roughness: Option<f32>, metallic: Option<f32>) -> Option<Self> {
+        if roughness.is_none() && metallic.is_none() {
+            return None;
+        }
+        Some(Self {
+            roughness: roughness.unwrap_or(DEFAULT_ROUGHNESS).clamp(0., 1.),
+            metallic: metallic.unwrap_or(0.).clamp(0., 1.),
+        })
+    }
+
+    /// Return whether the surface is so polished that its reflections are traced like a
+    /// mirror's rather than estimated by sampling the lights
+    pub fn is_smooth(&self) -> bool {
+        self.roughness < SMOOTH_ROUGHNESS
+    }
+
+    /// The squared width of the distribution, perceptually linear in the roughness
+    fn alpha_squared(&self) -> f32 {
+        self.roughness.powi(4).max(1e-7)
+    }
+
+    /// Return the density of the microfacets whose normal makes an angle of cosine `cos` with
+    /// the normal of the surface
+    pub fn distribution(&self, cos: f32) -> f32 {
+        let alpha_squared = self.alpha_squared();
+        alpha_squared / (PI * (cos.powi(2) * (alpha_squared - 1.) + 1.).powi(2))
+    }
+
+    /// Return the fraction of the microfacets visible from both the viewer and the light,
+    /// following Smith's separable model
+    pub fn shadowing(&self, cos_view: f32, cos_light: f32) -> f32 {
+        self.masking(cos_view) * self.masking(cos_light)
+    }
+
+    fn masking(&self, cos: f32) -> f32 {
+        let alpha_squared = self.alpha_squared();
+        2. * cos / (cos + (alpha_squared + (1. - alpha_squared) * cos.powi(2)).sqrt())
+    }
+
+    /// Return the normal of a random microfacet, with a density proportional to
+    /// `distribution(cos) * cos`
+    pub fn sample_normal(&self, normal: Vector, rng: &mut dyn RngCore) -> Vector {
+        let r1: f32 = rng.random_range(0.0..1.);
+        let r2: f32 = rng.random_range(0.0..1.);
+        let cos_squared = (1. - r1) / (1. + (self.alpha_squared() - 1.) * r1);
+        let sin = (1. - cos_squared).max(0.).sqrt();
+        let (tangent_1, tangent_2) = normal.tangents();
+        (tangent_1 * (sin * (2. * PI * r2).cos())
+            + tangent_2 * (sin * (2. * PI * r2).sin())
+            + normal * cos_squared.sqrt())
+        .normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_distribution_is_normalized() {
+        // the projected areas of the microfacets add up to the area of the surface
+        for roughness in [0.2, 0.5, 1.] {
+            let microfacet = Microfacet {
+                roughness,
+                metallic: 0.,
+            };
+            let steps = 100_000;
+            let integral: f32 = (0..steps)
+                .map(|step| {
+                    let cos = (step as f32 + 0.5) / steps as f32;
+                    microfacet.distribution(cos) * cos * 2. * PI / steps as f32
+                })
+                .sum();
+            assert!((integral - 1.).abs() < 1e-2, "{roughness}: {integral}");
+        }
+    }
+
+    #[test]
+    fn test_sample_normal() {
+        let normal = Vector {
+            x: 0.,
+            y: 0.,
+            z: -1.,
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut spread = |roughness| {
+            let microfacet = Microfacet {
+                roughness,
+                metallic: 1.,
+            };
+            (0..1000)
+                .map(|_| {
+                    let sample = microfacet.sample_normal(normal, &mut rng);
+                    assert!((sample.square_norm() - 1.).abs() < 1e-4);
+                    assert!(sample.dot(&normal) > 0.);
+                    1. - sample.dot(&normal)
+                })
+                .sum::<f32>()
+        };
+        assert!(spread(0.) < 1e-3);
+        assert!(spread(0.3) < spread(0.8));
+    }
+}
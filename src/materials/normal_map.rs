@@ -0,0 +1,139 @@
+use crate::textures::Texture;
+use crate::utils::color::Color;
+use crate::utils::intersection::Intersection;
+use crate::utils::vector::Vector;
+use std::sync::Arc;
+
+/// The step in texture coordinates of the finite differences of the bump maps
+const BUMP_DELTA: f32 = 1e-3;
+
+/// A perturbation of the shading normals of a material, which gives its surface detail
+/// without extra geometry
+#[derive(Debug, Clone)]
+pub enum NormalMap {
+    /// a tangent-space normal map, whose red, green and blue channels give the components
+    /// of the normal along `dpdu`, `dpdv` and the surface normal, from -1 to 1
+    Tangent(Arc<dyn Texture>),
+    /// a height map, displacing the surface along its normal by its scalar value times
+    /// `scale`, in scene units
+    Bump {
+        height: Arc<dyn Texture>,
+        scale: f32,
+    },
+}
+
+impl NormalMap {
+    /// Return the shading normal at an intersection. It stays on the side of the surface of
+    /// the normal, which is kept where the perturbation is undefined.
+    pub fn perturb(&self, intersection: &Intersection) -> Vector {
+        let normal = intersection.normal;
+        let perturbed = match self {
+            NormalMap::Tangent(texture) => {
+                let (tangent, bitangent) = tangent_frame(intersection);
+                let Color { r: x, g: y, b: z } =
+                    texture.value(intersection.uv).map(|c| 2. * c - 1.);
+                tangent * x + bitangent * y + normal * z
+            }
+            NormalMap::Bump { height, scale } => {
+                let [u, v] = intersection.uv;
+                let h = height.scalar(intersection.uv);
+                let dhdu = (height.scalar([u + BUMP_DELTA, v]) - h) / BUMP_DELTA * scale;
+                let dhdv = (height.scalar([u, v + BUMP_DELTA]) - h) / BUMP_DELTA * scale;
+                // the derivatives of the displaced surface, neglecting its curvature
+                let dpdu = intersection.dpdu + normal * dhdu;
+                let dpdv = intersection.dpdv + normal * dhdv;
+                // the texture coordinates may turn either way around the normal
+                let orientation = intersection
+                    .dpdu
+                    .cross(&intersection.dpdv)
+                    .dot(&normal)
+                    .signum();
+                dpdu.cross(&dpdv) * orientation
+            }
+        }
+        .normalize();
+        if perturbed.dot(&normal) > 0. {
+            perturbed
+        } else {
+            normal
+        }
+    }
+}
+
+/// Return the unit vectors along `dpdu` and orthogonal to it, towards `dpdv`, in the plane
+/// tangent to the surface
+fn tangent_frame(intersection: &Intersection) -> (Vector, Vector) {
+    let normal = intersection.normal;
+    let dpdu = intersection.dpdu - normal * normal.dot(&intersection.dpdu);
+    if dpdu.square_norm() < 1e-12 {
+        return normal.tangents();
+    }
+    let tangent = dpdu.normalize();
+    let bitangent = normal.cross(&tangent);
+    if bitangent.dot(&intersection.dpdv) < 0. {
+        (tangent, bitangent * -1.)
+    } else {
+        (tangent, bitangent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Material;
+    use crate::shapes::{Quad, Shape};
+    use crate::textures::{Constant, Gradient};
+    use crate::utils::ray::Ray;
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    /// Return the shading normal of a unit square in the xy plane, facing +z, hit at its
+    /// center
+    fn shading_normal(normal_map: NormalMap) -> Vector {
+        let quad = Quad {
+            origin: vector(0., 0., 0.),
+            edge_1: vector(1., 0., 0.),
+            edge_2: vector(0., 1., 0.),
+            material: Material::diffuse(Color::WHITE),
+        };
+        let ray = Ray::new(vector(0.5, 0.5, 1.), vector(0., 0., -1.));
+        normal_map.perturb(&quad.get_intersection(&ray).unwrap())
+    }
+
+    #[test]
+    fn test_tangent_normal_map() {
+        // the flat color of normal maps keeps the normal
+        let flat = shading_normal(NormalMap::Tangent(Arc::new(Constant(Color::from([
+            0.5, 0.5, 1.,
+        ])))));
+        assert_eq!(flat, vector(0., 0., 1.));
+        // the red channel tilts the normal along u
+        let tilted = shading_normal(NormalMap::Tangent(Arc::new(Constant(Color::from([
+            1., 0.5, 1.,
+        ])))));
+        assert!((tilted - vector(1., 0., 1.).normalize()).square_norm() < 1e-10);
+        // a normal below the surface is ignored
+        let below = shading_normal(NormalMap::Tangent(Arc::new(Constant(Color::from([
+            0.5, 0.5, 0.,
+        ])))));
+        assert_eq!(below, vector(0., 0., 1.));
+    }
+
+    #[test]
+    fn test_bump_map() {
+        // a slope rising along v by `scale` over the square
+        let slope = |scale| NormalMap::Bump {
+            height: Arc::new(Gradient {
+                start: Arc::new(Constant(Color::gray(0.))),
+                end: Arc::new(Constant(Color::gray(1.))),
+                direction: [0., 1.],
+            }),
+            scale,
+        };
+        let normal = shading_normal(slope(1.));
+        assert!((normal - vector(0., -1., 1.).normalize()).square_norm() < 1e-6);
+        assert!((shading_normal(slope(0.)) - vector(0., 0., 1.)).square_norm() < 1e-12);
+    }
+}
@@ -0,0 +1,712 @@
+//! Import of Wavefront OBJ models and their MTL materials.
+//!
+//! Supported statements are vertex positions (`v`), texture coordinates (`vt`), normals
+//! (`vn`), faces (`f`, triangulated as fans, so polygons are expected to be convex), groups
+//! and objects (`g`, `o`), material libraries (`mtllib`) and material selection (`usemtl`).
+//! Other statements, and comments from a `#` to the end of a line, are ignored. Material
+//! libraries which cannot be read are skipped and returned as warnings, so that their materials
+//! fall back to the default material, as unknown materials do.
+//!
+//! MTL materials are mapped onto [`Material`]: the diffuse color `Kd` gives the color, an
+//! illumination model with ray traced reflections (`illum 3` or `5`) makes the material
+//! specular, and a dissolve `d` below 1, a transparency `Tr` above 0 or an illumination
+//! model with refraction (`illum 4`, `6`, `7` or `9`) makes it transparent with the optical
+//! density `Ni` as refractive index. The emissive color `Ke` gives the emission, and the
+//! roughness `Pr` and metalness `Pm` of the PBR extension give a glossy coating. A diffuse
+//! texture map `map_Kd`, relative to the MTL file, replaces the diffuse color. A normal map
+//! `norm`, or else a bump map `bump` (or `map_Bump`) scaled by its `-bm` option, perturbs the
+//! normals. The other options of texture maps are ignored.
+use crate::materials::{Material, NormalMap};
+use crate::shapes::{MeshError, TriangleMesh};
+use crate::textures::{Bitmap, ColorSpace, Constant, Texture, WrapMode};
+use crate::utils::color::Color;
+use crate::utils::vector::Vector;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The refractive index of transparent MTL materials which do not provide one
+const DEFAULT_REFRACTIVE_INDEX: f32 = 1.5;
+
+/// An error raised while importing an OBJ model
+#[derive(Debug)]
+pub enum ObjError {
+    /// An OBJ or MTL file could not be read
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// A statement of an OBJ or MTL file is invalid
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// A group of faces could not be turned into a mesh
+    Mesh { group: String, error: MeshError },
+    /// The image of a texture map could not be loaded
+    Texture {
+        path: PathBuf,
+        error: image::ImageError,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, error } => write!(f, "could not read {}: {error}", path.display()),
+            ObjError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}, line {line}: {message}", path.display()),
+            ObjError::Mesh { group, error } => write!(f, "invalid group `{group}`: {error}"),
+            ObjError::Texture { path, error } => {
+                write!(f, "could not load {}: {error}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io { error, .. } => Some(error),
+            ObjError::Parse { .. } => None,
+            ObjError::Mesh { error, .. } => Some(error),
+            ObjError::Texture { error, .. } => Some(error),
+        }
+    }
+}
+
+/// A group of faces of an OBJ model sharing a material
+pub struct ObjGroup {
+    /// the name of the group or object, followed by the name of its material if the group
+    /// uses several of them
+    pub name: String,
+    pub mesh: TriangleMesh,
+}
+
+/// Import the groups of an OBJ model. Faces without a material, or all faces if `material`
+/// is given, use `material` or else `default_material`. The groups are returned along with
+/// the errors of the material libraries which could not be read, and were skipped.
+pub fn load_obj<P: AsRef<Path>>(
+    path: P,
+    material: Option<Material>,
+    default_material: Material,
+) -> Result<(Vec<ObjGroup>, Vec<ObjError>), ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let mut parser = ObjParser {
+        path,
+        positions: Vec::new(),
+        uvs: Vec::new(),
+        normals: Vec::new(),
+        materials: HashMap::new(),
+        groups: Vec::new(),
+        group: "default".to_string(),
+        material: None,
+        warnings: Vec::new(),
+    };
+    for (index, line) in source.lines().enumerate() {
+        parser.parse_line(line).map_err(|message| ObjError::Parse {
+            path: path.to_path_buf(),
+            line: index + 1,
+            message,
+        })?;
+    }
+
+    let materials_per_group =
+        parser
+            .groups
+            .iter()
+            .fold(HashMap::<&str, usize>::new(), |mut counts, group| {
+                *counts.entry(&group.name).or_default() += 1;
+                counts
+            });
+    let groups = parser
+        .groups
+        .iter()
+        .filter(|group| !group.triangles.is_empty())
+        .map(|group| {
+            let name = match &group.material {
+                Some(material) if materials_per_group[group.name.as_str()] > 1 => {
+                    format!("{}/{material}", group.name)
+                }
+                _ => group.name.clone(),
+            };
+            let group_material = material
+                .as_ref()
+                .or_else(|| {
+                    group
+                        .material
+                        .as_ref()
+                        .and_then(|name| parser.materials.get(name))
+                })
+                .unwrap_or(&default_material)
+                .clone();
+            let mesh = group
+                .to_mesh(
+                    &parser.positions,
+                    &parser.uvs,
+                    &parser.normals,
+                    group_material,
+                )
+                .map_err(|error| ObjError::Mesh {
+                    group: name.clone(),
+                    error,
+                })?;
+            Ok(ObjGroup { name, mesh })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((groups, parser.warnings))
+}
+
+/// Import the materials of an MTL file
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<HashMap<String, Material>, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+    for (index, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError::Parse {
+            path: path.to_path_buf(),
+            line: index + 1,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        if keyword == "newmtl" {
+            let name = tokens.collect::<Vec<_>>().join(" ");
+            if name.is_empty() {
+                return Err(error("missing material name".to_string()));
+            }
+            if let Some((name, material)) = current.replace((name, MtlMaterial::default())) {
+                materials.insert(name, material.to_material()?);
+            }
+            continue;
+        }
+        let Some((_, material)) = current.as_mut() else {
+            continue;
+        };
+        let mut number = || parse_number(&mut tokens, keyword).map_err(error);
+        match keyword {
+            "Kd" => material.diffuse = Color::from([number()?, number()?, number()?]),
+            "Ke" => material.emission = Color::from([number()?, number()?, number()?]),
+            "Pr" => material.roughness = Some(number()?),
+            "Pm" => material.metallic = Some(number()?),
+            "Ni" => material.optical_density = Some(number()?),
+            "d" => material.dissolve = number()?,
+            "Tr" => material.dissolve = 1. - number()?,
+            "illum" => material.illumination = number()? as u8,
+            "map_Kd" | "norm" | "bump" | "map_bump" | "map_Bump" => {
+                let (file, multiplier) = parse_map(tokens, keyword).map_err(error)?;
+                let file = path.parent().unwrap_or(Path::new("")).join(file);
+                match keyword {
+                    "map_Kd" => material.diffuse_map = Some(file),
+                    "norm" => material.normal_map = Some(file),
+                    _ => material.bump_map = Some((file, multiplier)),
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some((name, material)) = current {
+        materials.insert(name, material.to_material()?);
+    }
+    Ok(materials)
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    std::fs::read_to_string(path).map_err(|error| ObjError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+fn parse_number<'a, I: Iterator<Item = &'a str>>(
+    tokens: &mut I,
+    keyword: &str,
+) -> Result<f32, String> {
+    let token = tokens
+        .next()
+        .ok_or_else(|| format!("missing value for `{keyword}`"))?;
+    token
+        .parse()
+        .map_err(|_| format!("invalid number `{token}` for `{keyword}`"))
+}
+
+/// Return the file of a texture map statement, and its bump multiplier `-bm`, which defaults
+/// to 1
+fn parse_map<'a, I: Iterator<Item = &'a str>>(
+    tokens: I,
+    keyword: &str,
+) -> Result<(&'a str, f32), String> {
+    let tokens: Vec<&str> = tokens.collect();
+    let multiplier = match tokens.iter().position(|&token| token == "-bm") {
+        Some(index) => parse_number(&mut tokens[index + 1..].iter().copied(), "-bm")?,
+        None => 1.,
+    };
+    let file = tokens
+        .last()
+        .ok_or_else(|| format!("missing file for `{keyword}`"))?;
+    Ok((file, multiplier))
+}
+
+struct MtlMaterial {
+    diffuse: Color,
+    diffuse_map: Option<PathBuf>,
+    normal_map: Option<PathBuf>,
+    /// the height map and the multiplier of its heights
+    bump_map: Option<(PathBuf, f32)>,
+    emission: Color,
+    roughness: Option<f32>,
+    metallic: Option<f32>,
+    optical_density: Option<f32>,
+    dissolve: f32,
+    illumination: u8,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: Color::gray(0.8),
+            diffuse_map: None,
+            normal_map: None,
+            bump_map: None,
+            emission: Color::BLACK,
+            roughness: None,
+            metallic: None,
+            optical_density: None,
+            dissolve: 1.,
+            illumination: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    fn to_material(&self) -> Result<Material, ObjError> {
+        let transparent = self.dissolve < 1. || matches!(self.illumination, 4 | 6 | 7 | 9);
+        let color = match &self.diffuse_map {
+            Some(path) => open_map(path, ColorSpace::Auto)?,
+            None => Arc::new(Constant(self.diffuse.map(|c| c.clamp(0., 1.)))),
+        };
+        // the normal map prevails over the bump map
+        let normal_map = match (&self.normal_map, &self.bump_map) {
+            (Some(path), _) => Some(NormalMap::Tangent(open_map(path, ColorSpace::Linear)?)),
+            (None, Some((path, multiplier))) => Some(NormalMap::Bump {
+                height: open_map(path, ColorSpace::Linear)?,
+                scale: *multiplier,
+            }),
+            (None, None) => None,
+        };
+        let constant = |value: f32| Arc::new(Constant(Color::gray(value))) as Arc<dyn Texture>;
+        Ok(Material {
+            color,
+            specular: matches!(self.illumination, 3 | 5),
+            roughness: self.roughness.map(constant),
+            metallic: self.metallic.map(constant),
+            normal_map,
+            refractive_index: if transparent {
+                self.optical_density.unwrap_or(DEFAULT_REFRACTIVE_INDEX)
+            } else {
+                0.
+            },
+            emission: self.emission,
+            outer_refractive_index: 1.,
+            absorption: Color::BLACK,
+        })
+    }
+}
+
+/// Load the image of a texture map, tiled over the texture coordinates. The normal and bump
+/// maps hold linear values, while the colors are usually sRGB encoded.
+fn open_map(path: &Path, color_space: ColorSpace) -> Result<Arc<dyn Texture>, ObjError> {
+    match Bitmap::open(path, WrapMode::Repeat, color_space) {
+        Ok(bitmap) => Ok(Arc::new(bitmap)),
+        Err(error) => Err(ObjError::Texture {
+            path: path.to_path_buf(),
+            error,
+        }),
+    }
+}
+
+/// The indices of the position, texture coordinates and normal of a face vertex
+type VertexIndices = (usize, Option<usize>, Option<usize>);
+
+struct Group {
+    name: String,
+    material: Option<String>,
+    triangles: Vec<[VertexIndices; 3]>,
+}
+
+impl Group {
+    /// Build the mesh of the group, merging the face vertices which share all their indices.
+    /// Normals and texture coordinates are dropped unless every vertex has them.
+    fn to_mesh(
+        &self,
+        positions: &[Vector],
+        uvs: &[[f32; 2]],
+        normals: &[Vector],
+        material: Material,
+    ) -> Result<TriangleMesh, MeshError> {
+        let mut vertices: HashMap<VertexIndices, usize> = HashMap::new();
+        let mut mesh_positions = Vec::new();
+        let mut mesh_uvs = Vec::new();
+        let mut mesh_normals = Vec::new();
+        let mut triangles = Vec::with_capacity(self.triangles.len());
+        for triangle in &self.triangles {
+            triangles.push(triangle.map(|indices| {
+                *vertices.entry(indices).or_insert_with(|| {
+                    let (position, uv, normal) = indices;
+                    mesh_positions.push(positions[position]);
+                    mesh_uvs.push(uv.map(|uv| uvs[uv]));
+                    mesh_normals.push(normal.map(|normal| normals[normal]));
+                    mesh_positions.len() - 1
+                })
+            }));
+        }
+        let mesh_uvs = mesh_uvs.into_iter().collect::<Option<Vec<_>>>();
+        let mesh_normals = mesh_normals.into_iter().collect::<Option<Vec<_>>>();
+        TriangleMesh::new(mesh_positions, mesh_normals, mesh_uvs, triangles, material)
+    }
+}
+
+struct ObjParser<'a> {
+    path: &'a Path,
+    positions: Vec<Vector>,
+    uvs: Vec<[f32; 2]>,
+    normals: Vec<Vector>,
+    materials: HashMap<String, Material>,
+    groups: Vec<Group>,
+    /// the name of the current group
+    group: String,
+    /// the name of the current material
+    material: Option<String>,
+    /// the material libraries which could not be read
+    warnings: Vec<ObjError>,
+}
+
+impl ObjParser<'_> {
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            return Ok(());
+        };
+        match keyword {
+            "v" => {
+                let mut number = || parse_number(&mut tokens, keyword);
+                self.positions.push(Vector {
+                    x: number()?,
+                    y: number()?,
+                    z: number()?,
+                });
+            }
+            "vt" => {
+                let u = parse_number(&mut tokens, keyword)?;
+                // the second coordinate is optional for 1D textures
+                let v = match tokens.next() {
+                    Some(token) => token
+                        .parse()
+                        .map_err(|_| format!("invalid number `{token}` for `vt`"))?,
+                    None => 0.,
+                };
+                self.uvs.push([u, v]);
+            }
+            "vn" => {
+                let mut number = || parse_number(&mut tokens, keyword);
+                self.normals.push(
+                    Vector {
+                        x: number()?,
+                        y: number()?,
+                        z: number()?,
+                    }
+                    .normalize(),
+                );
+            }
+            "f" => {
+                let vertices = tokens
+                    .map(|token| self.parse_vertex(token))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if vertices.len() < 3 {
+                    return Err(format!(
+                        "a face needs at least 3 vertices, got {}",
+                        vertices.len()
+                    ));
+                }
+                let group = self.current_group();
+                for i in 1..vertices.len() - 1 {
+                    group
+                        .triangles
+                        .push([vertices[0], vertices[i], vertices[i + 1]]);
+                }
+            }
+            "g" | "o" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                self.group = if name.is_empty() {
+                    "default".to_string()
+                } else {
+                    name
+                };
+            }
+            "usemtl" => {
+                // unknown materials fall back to the default material
+                self.material = Some(tokens.collect::<Vec<_>>().join(" "));
+            }
+            "mtllib" => {
+                for library in tokens {
+                    let path = self.path.with_file_name(library);
+                    match load_mtl(&path) {
+                        Ok(materials) => self.materials.extend(materials),
+                        // like unknown materials, the materials of a missing library fall back
+                        // to the default material
+                        Err(err @ ObjError::Io { .. }) => self.warnings.push(err),
+                        Err(err) => return Err(err.to_string()),
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Return the group of the current group name and material, creating it if needed
+    fn current_group(&mut self) -> &mut Group {
+        let position = self
+            .groups
+            .iter()
+            .position(|group| group.name == self.group && group.material == self.material);
+        let position = position.unwrap_or_else(|| {
+            self.groups.push(Group {
+                name: self.group.clone(),
+                material: self.material.clone(),
+                triangles: Vec::new(),
+            });
+            self.groups.len() - 1
+        });
+        &mut self.groups[position]
+    }
+
+    /// Parse a face vertex such as `1`, `1/2`, `1//3` or `1/2/3`
+    fn parse_vertex(&self, token: &str) -> Result<VertexIndices, String> {
+        let mut indices = token.split('/');
+        let position = resolve_index(indices.next(), self.positions.len(), "position")?
+            .ok_or_else(|| format!("missing position index in `{token}`"))?;
+        let uv = resolve_index(indices.next(), self.uvs.len(), "texture coordinates")?;
+        let normal = resolve_index(indices.next(), self.normals.len(), "normal")?;
+        Ok((position, uv, normal))
+    }
+}
+
+/// Convert a 1-based OBJ index, which is relative to the end of the list when negative,
+/// into a 0-based index
+fn resolve_index(
+    token: Option<&str>,
+    len: usize,
+    attribute: &str,
+) -> Result<Option<usize>, String> {
+    let token = match token {
+        None | Some("") => return Ok(None),
+        Some(token) => token,
+    };
+    let index: isize = token
+        .parse()
+        .map_err(|_| format!("invalid {attribute} index `{token}`"))?;
+    let resolved = if index < 0 {
+        len as isize + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved as usize >= len {
+        return Err(format!("{attribute} index {index} is out of bounds"));
+    }
+    Ok(Some(resolved as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::Shape;
+    use crate::utils::ray::Ray;
+
+    fn white() -> Material {
+        Material::diffuse(Color::WHITE)
+    }
+
+    /// Write files to a new temporary directory and return its path
+    fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("ray-tracer-obj-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for (file, content) in files {
+            std::fs::write(directory.join(file), content).unwrap();
+        }
+        directory
+    }
+
+    const QUAD: &str = "
+mtllib quad.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+g front
+usemtl red
+f 1/1/1 2/2/1 3/3/1 4/4/1
+g back
+usemtl glass
+f -1 -2 -3
+";
+
+    const MTL: &str = "
+newmtl red
+Kd 1 0 0
+newmtl glass
+Kd 1 1 1
+illum 7
+Ni 1.33
+";
+
+    #[test]
+    fn test_load_obj() {
+        let directory = write_files("quad", &[("quad.obj", QUAD), ("quad.mtl", MTL)]);
+        let (groups, warnings) = load_obj(directory.join("quad.obj"), None, white()).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "front");
+        assert_eq!(groups[0].mesh.triangles_count(), 2);
+        assert_eq!(
+            groups[0].mesh.get_material().color.value([0., 0.]),
+            Color::from([1., 0., 0.])
+        );
+        assert_eq!(groups[0].mesh.get_uv(1, 0.5, 0.5), [0.5, 1.]);
+        assert_eq!(groups[1].name, "back");
+        assert_eq!(groups[1].mesh.get_material().refractive_index, 1.33);
+
+        let ray = Ray::new(
+            Vector {
+                x: 0.75,
+                y: 0.25,
+                z: 1.,
+            },
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: -1.,
+            },
+        );
+        let intersection = groups[0].mesh.get_intersection(&ray).unwrap();
+        assert_eq!(intersection.d, 1.);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_missing_library() {
+        let directory = write_files("missing", &[("quad.obj", QUAD)]);
+        let (groups, warnings) = load_obj(directory.join("quad.obj"), None, white()).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert!(matches!(&warnings[..], [ObjError::Io { path, .. }] if path.ends_with("quad.mtl")));
+        assert!(groups
+            .iter()
+            .all(|group| group.mesh.get_material().color.value([0., 0.]) == Color::WHITE));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_comments() {
+        let obj = "
+# a triangle
+v 0 0 0
+v 1 0 0
+v 1 1 0
+g front # the group
+f 1 2 3 # the face
+";
+        let directory = write_files("comments", &[("comments.obj", obj)]);
+        let (groups, _) = load_obj(directory.join("comments.obj"), None, white()).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "front");
+        assert_eq!(groups[0].mesh.triangles_count(), 1);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_mtl_comments() {
+        let mtl = "
+# the materials of the quad
+newmtl red # the front
+Kd 1 0 0 # pure red
+newmtl glass
+illum 7 # refractive
+Ni 1.33
+";
+        let directory = write_files("mtl-comments", &[("quad.mtl", mtl)]);
+        let materials = load_mtl(directory.join("quad.mtl")).unwrap();
+        assert_eq!(
+            materials["red"].color.value([0., 0.]),
+            Color::from([1., 0., 0.])
+        );
+        assert_eq!(materials["glass"].refractive_index, 1.33);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_material_override() {
+        let directory = write_files("override", &[("quad.obj", QUAD), ("quad.mtl", MTL)]);
+        let (groups, _) = load_obj(directory.join("quad.obj"), Some(white()), white()).unwrap();
+        assert!(groups
+            .iter()
+            .all(|group| group.mesh.get_material().color.value([0., 0.]) == Color::WHITE));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_texture_map() {
+        let mtl = "newmtl red\nKd 1 1 1\nmap_Kd -s 1 1 1 red.png\n";
+        let directory = write_files("texture", &[("quad.obj", QUAD), ("quad.mtl", mtl)]);
+        image::RgbImage::from_pixel(2, 2, image::Rgb([255, 0, 0]))
+            .save(directory.join("red.png"))
+            .unwrap();
+        let (groups, _) = load_obj(directory.join("quad.obj"), None, white()).unwrap();
+        let color = groups[0].mesh.get_material().color.value([0.3, 0.6]);
+        assert_eq!(color, Color::from([1., 0., 0.]));
+
+        std::fs::remove_file(directory.join("red.png")).unwrap();
+        assert!(matches!(
+            load_mtl(directory.join("quad.mtl")),
+            Err(ObjError::Texture { .. })
+        ));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_index() {
+        let directory = write_files("invalid", &[("invalid.obj", "v 0 0 0\nv 1 0 0\nf 1 2 3\n")]);
+        match load_obj(directory.join("invalid.obj"), None, white()) {
+            Err(ObjError::Parse { line, message, .. }) => {
+                assert_eq!(line, 3);
+                assert_eq!(message, "position index 3 is out of bounds");
+            }
+            _ => panic!("expected a parse error"),
+        }
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_index() {
+        assert_eq!(resolve_index(Some("1"), 3, "position"), Ok(Some(0)));
+        assert_eq!(resolve_index(Some("-1"), 3, "position"), Ok(Some(2)));
+        assert_eq!(resolve_index(Some(""), 3, "normal"), Ok(None));
+        assert!(resolve_index(Some("0"), 3, "position").is_err());
+    }
+}
@@ -0,0 +1,480 @@
+//! Writing of the rendered images, whose format is chosen from the extension of the path.
+//!
+//! The 8-bit formats supported by the `image` crate, such as PNG, JPEG or TGA, receive the
+//! colors after the exposure, the tone mapping and the sRGB encoding of the settings. The
+//! floating point formats keep the linear colors of the framebuffer, unclamped and without
+//! exposure, for compositing: OpenEXR (`.exr`), in half or full float depending on
+//! [`RenderSettings::exr_precision`], Radiance HDR (`.hdr`) and the portable float map
+//! (`.pfm`).
+//!
+//! The passes of a render, see [`crate::aovs`], are written as layers of the OpenEXR images,
+//! whose channels are named after the pass, as `depth.Z` or `normal.R`. With the other
+//! formats, each pass is written to an image of its own, whose name inserts the name of the
+//! pass before the extension, as `image.depth.png`.
+use crate::aovs::{Aov, Render};
+use crate::framebuffer::Framebuffer;
+use crate::settings::RenderSettings;
+use crate::utils::color::Color;
+use exr::prelude::{
+    f16, AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes,
+    WritableImage,
+};
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The file formats the images can be saved in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// OpenEXR, holding named channels of half or full floats
+    Exr,
+    /// Radiance RGBE, sharing an 8-bit exponent between the channels
+    Hdr,
+    /// the portable float map, of uncompressed 32-bit floats
+    Pfm,
+    /// an 8-bit format of the `image` crate
+    Ldr(ImageFormat),
+}
+
+impl OutputFormat {
+    /// Return the format matching the extension of a path
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, OutputError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("exr") => Ok(OutputFormat::Exr),
+            Some("hdr") => Ok(OutputFormat::Hdr),
+            Some("pfm") => Ok(OutputFormat::Pfm),
+            _ => match ImageFormat::from_path(path) {
+                Ok(format) if format.writing_enabled() => Ok(OutputFormat::Ldr(format)),
+                _ => Err(OutputError::UnsupportedFormat(path.to_path_buf())),
+            },
+        }
+    }
+}
+
+/// The precision of the channels of the OpenEXR images
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExrPrecision {
+    /// 16-bit floats, precise to about 3 decimal digits up to 65504, which is enough for
+    /// colors and halves the size of the files
+    #[default]
+    Half,
+    /// 32-bit floats, for depths and positions
+    Full,
+}
+
+impl ExrPrecision {
+    pub const ALL: [ExrPrecision; 2] = [ExrPrecision::Half, ExrPrecision::Full];
+
+    /// The name of the precision, in scene files and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExrPrecision::Half => "half",
+            ExrPrecision::Full => "full",
+        }
+    }
+
+    fn samples(&self, values: Vec<f32>) -> FlatSamples {
+        match self {
+            ExrPrecision::Half => FlatSamples::F16(values.into_iter().map(f16::from_f32).collect()),
+            ExrPrecision::Full => FlatSamples::F32(values),
+        }
+    }
+}
+
+impl fmt::Display for ExrPrecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ExrPrecision {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|precision| precision.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(ExrPrecision::name).collect();
+                format!(
+                    "unknown precision `{name}`, expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// An error raised while saving an image
+#[derive(Debug)]
+pub enum OutputError {
+    /// The extension of the path does not match a format which can be written
+    UnsupportedFormat(PathBuf),
+    /// The file could not be written
+    Io(std::io::Error),
+    /// The `image` crate could not encode the image
+    Image(image::ImageError),
+    /// The OpenEXR image could not be encoded
+    Exr(exr::error::Error),
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputError::UnsupportedFormat(path) => {
+                write!(
+                    f,
+                    "no image format matches the extension of {}",
+                    path.display()
+                )
+            }
+            OutputError::Io(err) => write!(f, "{err}"),
+            OutputError::Image(err) => write!(f, "{err}"),
+            OutputError::Exr(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OutputError::UnsupportedFormat(_) => None,
+            OutputError::Io(err) => Some(err),
+            OutputError::Image(err) => Some(err),
+            OutputError::Exr(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for OutputError {
+    fn from(err: std::io::Error) -> Self {
+        OutputError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for OutputError {
+    fn from(err: image::ImageError) -> Self {
+        OutputError::Image(err)
+    }
+}
+
+impl From<exr::error::Error> for OutputError {
+    fn from(err: exr::error::Error) -> Self {
+        OutputError::Exr(err)
+    }
+}
+
+/// Save a framebuffer, in the format given by the extension of the path
+pub fn save<P: AsRef<Path>>(
+    framebuffer: &Framebuffer,
+    path: P,
+    settings: &RenderSettings,
+) -> Result<(), OutputError> {
+    let path = path.as_ref();
+    match OutputFormat::from_path(path)? {
+        OutputFormat::Exr => write_exr(
+            path,
+            framebuffer.width(),
+            framebuffer.height(),
+            rgb_channels(framebuffer, settings.exr_precision),
+        ),
+        OutputFormat::Hdr => Ok(DynamicImage::ImageRgb32F(framebuffer.to_rgb32f())
+            .save_with_format(path, ImageFormat::Hdr)?),
+        OutputFormat::Pfm => write_pfm(path, framebuffer),
+        OutputFormat::Ldr(format) => Ok(framebuffer
+            .to_rgb8(settings.exposure, settings.tone_mapping)
+            .save_with_format(path, format)?),
+    }
+}
+
+/// Save the beauty image and the passes of a render, in the format given by the extension of
+/// the path
+pub fn save_render<P: AsRef<Path>>(
+    render: &Render,
+    path: P,
+    settings: &RenderSettings,
+) -> Result<(), OutputError> {
+    let path = path.as_ref();
+    let format = OutputFormat::from_path(path)?;
+    if format == OutputFormat::Exr {
+        let mut channels = rgb_channels(&render.beauty, settings.exr_precision);
+        for (aov, framebuffer) in &render.aovs {
+            channels.extend(aov_channels(*aov, framebuffer, settings.exr_precision));
+        }
+        let (width, height) = (render.beauty.width(), render.beauty.height());
+        return write_exr(path, width, height, channels);
+    }
+    save(&render.beauty, path, settings)?;
+    // without a coverage, every ray is taken as hitting a surface
+    let coverage = render
+        .coverage
+        .clone()
+        .unwrap_or_else(|| render.beauty.map(|_| Color::WHITE));
+    for (aov, framebuffer) in &render.aovs {
+        let path = aov_path(path, *aov);
+        match format {
+            OutputFormat::Ldr(format) => aov
+                .to_rgb8(framebuffer, &coverage, settings)
+                .save_with_format(path, format)?,
+            _ => save(framebuffer, path, settings)?,
+        }
+    }
+    Ok(())
+}
+
+/// Return the path of the image of a pass, whose name inserts the name of the pass before
+/// the extension of the path of the beauty image
+pub fn aov_path(path: &Path, aov: Aov) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(extension) => {
+            path.with_file_name(format!("{stem}.{aov}.{}", extension.to_string_lossy()))
+        }
+        None => path.with_file_name(format!("{stem}.{aov}")),
+    }
+}
+
+/// Return the red, green and blue channels of a framebuffer
+fn rgb_channels(framebuffer: &Framebuffer, precision: ExrPrecision) -> Vec<(String, FlatSamples)> {
+    ["R", "G", "B"]
+        .into_iter()
+        .enumerate()
+        .map(|(channel, name)| {
+            let values = channel_values(framebuffer, channel);
+            (name.to_string(), precision.samples(values))
+        })
+        .collect()
+}
+
+/// Return the channels of a pass, named after it. The identifiers are stored as integers.
+fn aov_channels(
+    aov: Aov,
+    framebuffer: &Framebuffer,
+    precision: ExrPrecision,
+) -> Vec<(String, FlatSamples)> {
+    aov.channels()
+        .iter()
+        .enumerate()
+        .map(|(channel, name)| {
+            let values = channel_values(framebuffer, channel);
+            let samples = if aov.is_id() {
+                FlatSamples::U32(values.into_iter().map(|id| id as u32).collect())
+            } else {
+                precision.samples(values)
+            };
+            (format!("{aov}.{name}"), samples)
+        })
+        .collect()
+}
+
+/// Return the values of a channel of the pixels of a framebuffer, row by row
+fn channel_values(framebuffer: &Framebuffer, channel: usize) -> Vec<f32> {
+    framebuffer
+        .pixels()
+        .iter()
+        .map(|color| color.to_array()[channel])
+        .collect()
+}
+
+/// Write named channels, holding the values of the pixels row by row, to an OpenEXR file
+fn write_exr(
+    path: &Path,
+    width: u32,
+    height: u32,
+    channels: Vec<(String, FlatSamples)>,
+) -> Result<(), OutputError> {
+    let channels = channels
+        .into_iter()
+        .map(|(name, samples)| AnyChannel::new(name.as_str(), samples))
+        .collect::<Vec<_>>();
+    let layer = Layer::new(
+        (width as usize, height as usize),
+        LayerAttributes::default(),
+        Encoding::FAST_LOSSLESS,
+        AnyChannels::sort(channels.into()),
+    );
+    Image::from_layer(layer).write().to_file(path)?;
+    Ok(())
+}
+
+/// Write a framebuffer to a portable float map, whose rows go from the bottom to the top and
+/// whose negative scale marks little-endian floats
+fn write_pfm(path: &Path, framebuffer: &Framebuffer) -> Result<(), OutputError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(
+        file,
+        "PF\n{} {}\n-1.0\n",
+        framebuffer.width(),
+        framebuffer.height()
+    )?;
+    for y in (0..framebuffer.height()).rev() {
+        for x in 0..framebuffer.width() {
+            for value in framebuffer.get_pixel(x, y).to_array() {
+                file.write_all(&value.to_le_bytes())?;
+            }
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::color::Color;
+    use exr::prelude::read_all_flat_layers_from_file;
+
+    /// A 2×1 framebuffer, with a color brighter than white on the right
+    fn framebuffer() -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(2, 1);
+        framebuffer.put_pixel(0, 0, Color::from([0.25, 0.5, 0.75]));
+        framebuffer.put_pixel(1, 0, Color::from([4., 100., 0.]));
+        framebuffer
+    }
+
+    fn temporary_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ray-tracer-output-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn test_output_formats() {
+        assert_eq!(OutputFormat::from_path("a.EXR").unwrap(), OutputFormat::Exr);
+        assert_eq!(OutputFormat::from_path("a.hdr").unwrap(), OutputFormat::Hdr);
+        assert_eq!(OutputFormat::from_path("a.pfm").unwrap(), OutputFormat::Pfm);
+        assert_eq!(
+            OutputFormat::from_path("a.png").unwrap(),
+            OutputFormat::Ldr(ImageFormat::Png)
+        );
+        assert!(matches!(
+            OutputFormat::from_path("a.txt"),
+            Err(OutputError::UnsupportedFormat(_))
+        ));
+        assert!(OutputFormat::from_path("a").is_err());
+    }
+
+    #[test]
+    fn test_exr() {
+        for precision in ExrPrecision::ALL {
+            assert_eq!(precision.name().parse(), Ok(precision));
+            let path = temporary_path(&format!("{precision}.exr"));
+            let settings = RenderSettings {
+                exr_precision: precision,
+                ..RenderSettings::default()
+            };
+            save(&framebuffer(), &path, &settings).unwrap();
+            let image = read_all_flat_layers_from_file(&path).unwrap();
+            let channels = &image.layer_data[0].channel_data.list;
+            let names: Vec<String> = channels.iter().map(|c| c.name.to_string()).collect();
+            assert_eq!(names, ["B", "G", "R"]);
+            let green = &channels[1].sample_data;
+            assert_eq!(
+                matches!(green, FlatSamples::F16(_)),
+                precision == ExrPrecision::Half
+            );
+            // the colors are neither clamped nor encoded
+            assert_eq!(green.value_by_flat_index(1).to_f32(), 100.);
+            assert_eq!(green.value_by_flat_index(0).to_f32(), 0.5);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_exr_layers() {
+        let path = temporary_path("layers.exr");
+        let mut depth = Framebuffer::new(2, 1);
+        depth.put_pixel(0, 0, Color::gray(12.5));
+        depth.put_pixel(1, 0, Color::gray(f32::INFINITY));
+        let render = Render {
+            beauty: framebuffer(),
+            aovs: vec![
+                (Aov::Depth, depth),
+                (
+                    Aov::ObjectId,
+                    Framebuffer::new(2, 1).map(|_| Color::gray(3.)),
+                ),
+                (Aov::Normal, framebuffer()),
+            ],
+            coverage: None,
+        };
+        save_render(&render, &path, &RenderSettings::default()).unwrap();
+        let image = read_all_flat_layers_from_file(&path).unwrap();
+        let channels = &image.layer_data[0].channel_data.list;
+        let names: Vec<String> = channels.iter().map(|c| c.name.to_string()).collect();
+        assert_eq!(
+            names,
+            [
+                "B",
+                "G",
+                "R",
+                "depth.Z",
+                "normal.B",
+                "normal.G",
+                "normal.R",
+                "object_id.id"
+            ]
+        );
+        let depth = &channels[3].sample_data;
+        assert_eq!(depth.value_by_flat_index(0).to_f32(), 12.5);
+        assert!(depth.value_by_flat_index(1).to_f32().is_infinite());
+        assert!(matches!(channels[7].sample_data, FlatSamples::U32(_)));
+        assert_eq!(channels[7].sample_data.value_by_flat_index(1).to_f32(), 3.);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_aov_images() {
+        let path = temporary_path("passes.png");
+        let render = Render {
+            beauty: framebuffer(),
+            aovs: vec![(
+                Aov::Shadow,
+                Framebuffer::new(2, 1).map(|_| Color::gray(0.5)),
+            )],
+            coverage: None,
+        };
+        save_render(&render, &path, &RenderSettings::default()).unwrap();
+        let aov_path = aov_path(&path, Aov::Shadow);
+        assert!(aov_path.to_string_lossy().ends_with("passes.shadow.png"));
+        let shadow = image::open(&aov_path).unwrap().to_rgb8();
+        // the masks are not encoded in sRGB
+        assert_eq!(shadow.get_pixel(0, 0).0, [128; 3]);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(aov_path).unwrap();
+    }
+
+    #[test]
+    fn test_hdr() {
+        let path = temporary_path("image.hdr");
+        save(&framebuffer(), &path, &RenderSettings::default()).unwrap();
+        let image = image::open(&path).unwrap().to_rgb32f();
+        let pixel = image.get_pixel(1, 0).0;
+        // the channels share an exponent, so the small ones lose precision
+        assert!((pixel[1] - 100.).abs() < 1. && (pixel[0] - 4.).abs() < 0.5);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_pfm() {
+        let path = temporary_path("image.pfm");
+        save(&framebuffer(), &path, &RenderSettings::default()).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let header = b"PF\n2 1\n-1.0\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let values: Vec<f32> = bytes[header.len()..]
+            .chunks(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(values, [0.25, 0.5, 0.75, 4., 100., 0.]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
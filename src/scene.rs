@@ -1,174 +1,599 @@
-use crate::constants::{BLACK, DIFFUSED, DIFFUSED_SAMPLES_COUNT, IMAGE_SIZE, MAX_BOUNCES};
+use crate::aovs::{Aov, Render};
+use crate::bvh::Bvh;
+use crate::camera::Camera;
+use crate::constants::TILE_SIZE;
+use crate::framebuffer::Framebuffer;
+use crate::integrators::{self, Integrator};
+use crate::lights::{Light, LightSample};
+use crate::materials::Material;
+use crate::output::{self, OutputError};
+use crate::settings::RenderSettings;
 use crate::shapes::Shape;
+use crate::utils::aabb::Aabb;
+use crate::utils::color::Color;
 use crate::utils::intersection::Intersection;
 use crate::utils::ray::Ray;
 use crate::utils::vector::Vector;
 use rand::prelude::*;
-use std::f32::consts::{E, PI};
-use tqdm::Iter;
-
-pub struct Camera {
-    pub point: Vector,
-    /// field of vision, which is the opening angle, in radians
-    pub fov: f32,
-    pub direction: Vector,
-    pub up: Vector,
-    pub right: Vector,
+use rayon::prelude::*;
+use rayon::{ThreadPoolBuildError, ThreadPoolBuilder};
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// An error raised while rendering a scene
+#[derive(Debug)]
+pub enum RenderError {
+    /// The render threads could not be started
+    Threads(ThreadPoolBuildError),
+    /// The image could not be saved
+    Output(OutputError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Threads(err) => write!(f, "could not start the render threads: {err}"),
+            RenderError::Output(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::Threads(err) => Some(err),
+            RenderError::Output(err) => Some(err),
+        }
+    }
 }
 
-pub struct Light {
-    pub origin: Vector,
-    pub intensity: u32,
+impl From<ThreadPoolBuildError> for RenderError {
+    fn from(err: ThreadPoolBuildError) -> Self {
+        RenderError::Threads(err)
+    }
+}
+
+impl From<OutputError> for RenderError {
+    fn from(err: OutputError) -> Self {
+        RenderError::Output(err)
+    }
 }
 
 pub struct Scene {
-    pub shapes: Vec<Box<dyn Shape>>,
-    pub light: Light,
+    shapes: Vec<Box<dyn Shape>>,
+    /// the hierarchy of the bounding boxes of `shapes`
+    bvh: Bvh,
+    /// the identifier of the material of each shape, from 1, see [`Scene::material_id`]
+    material_ids: Vec<u32>,
+    pub lights: Vec<Box<dyn Light>>,
     pub camera: Camera,
+    /// the settings the scene is meant to be rendered with
+    pub settings: RenderSettings,
 }
 
 impl Scene {
-    pub fn generate_image(&self) {
-        println!("[*] Generating image...");
-        let mut imgbuf = image::ImageBuffer::new(IMAGE_SIZE.0, IMAGE_SIZE.1);
-        let d = (IMAGE_SIZE.0 / 2) as f32 / (self.camera.fov / 2.).tan();
-        for (x, y, pixel) in imgbuf.enumerate_pixels_mut().tqdm() {
-            let mut ray = self.generate_ray(y, x, d);
-            let mut color = self.get_color(&mut ray, MAX_BOUNCES, false);
-            let mut diffused_color = [0f32; 3];
-            for _ in 0..DIFFUSED_SAMPLES_COUNT {
-                ray = self.generate_ray(y, x, d);
-                let result = self.get_color(&mut ray, 1, true);
-                diffused_color[0] += result[0];
-                diffused_color[1] += result[1];
-                diffused_color[2] += result[2];
-            }
-            color[0] += diffused_color[0] / DIFFUSED_SAMPLES_COUNT as f32;
-            color[1] += diffused_color[1] / DIFFUSED_SAMPLES_COUNT as f32;
-            color[2] += diffused_color[2] / DIFFUSED_SAMPLES_COUNT as f32;
-            color[0] = color[0].powf(1. / 2.2);
-            color[1] = color[1].powf(1. / 2.2);
-            color[2] = color[2].powf(1. / 2.2);
-            *pixel = image::Rgb([color[0] as u8, color[1] as u8, color[2] as u8]);
-        }
-        imgbuf.save("generated.png").unwrap();
-        println!("[+] Successfully generated image");
-    }
-    fn generate_ray(&self, i: u32, j: u32, d: f32) -> Ray {
-        let mut rng = rand::rng();
-        let x: f32 = rng.random_range(0.0..1.);
-        let y: f32 = rng.random_range(0.0..1.);
-        let r = (-2. * x.log(E)).sqrt();
-        let u = r * (2. * PI * y).cos() / 2.;
-        let v = r * (2. * PI * y).sin() / 2.;
-        Ray {
-            origin: self.camera.point,
-            direction: (self.camera.right * (j as f32 - (IMAGE_SIZE.0 as f32) / 2. - 0.5 + u)
-                + self.camera.up * (i as f32 - IMAGE_SIZE.1 as f32 / 2. - 0.5 + v)
-                + self.camera.direction * (-d))
-                .normalize(),
+    pub fn new(
+        shapes: Vec<Box<dyn Shape>>,
+        lights: Vec<Box<dyn Light>>,
+        camera: Camera,
+        settings: RenderSettings,
+    ) -> Self {
+        let bounds: Vec<Aabb> = shapes.iter().map(|shape| shape.bounds()).collect();
+        let mut materials: Vec<&Material> = Vec::new();
+        let material_ids = shapes
+            .iter()
+            .map(|shape| {
+                let material = shape.get_material();
+                let index = match materials.iter().position(|other| other.is_same(material)) {
+                    Some(index) => index,
+                    None => {
+                        materials.push(material);
+                        materials.len() - 1
+                    }
+                };
+                index as u32 + 1
+            })
+            .collect();
+        Self {
+            bvh: Bvh::build(&bounds),
+            material_ids,
+            shapes,
+            lights,
+            camera,
+            settings,
         }
     }
 
-    fn get_color(&self, ray: &mut Ray, remaining_bounces: u8, diffused: bool) -> [f32; 3] {
-        let mut intersection = match self.intersect(&ray) {
-            Some(intersection) => intersection,
-            None => return BLACK
-        };
+    pub fn shapes(&self) -> &[Box<dyn Shape>] {
+        &self.shapes
+    }
 
-        // fixes a bug with specular materials
-        intersection.intersection += intersection.normal * 0.0001;
+    /// Return the identifier of the material of a shape, given by its index. The identifiers
+    /// start from 1 and are shared by the shapes whose materials are identical, as the ones
+    /// referencing the same material of a scene file.
+    pub fn material_id(&self, index: usize) -> u32 {
+        self.material_ids[index]
+    }
+
+    /// Render the scene into a floating-point framebuffer, with the integrator selected by the
+    /// settings
+    pub fn render(&self, settings: &RenderSettings) -> Result<Framebuffer, RenderError> {
+        self.render_with(&*integrators::from_settings(settings), settings)
+    }
+
+    /// Render the scene into a floating-point framebuffer, with any integrator.
+    ///
+    /// The image is split into tiles rendered in parallel, each with its own random number
+    /// generator, so that a seeded render does not depend on the number of threads.
+    pub fn render_with(
+        &self,
+        integrator: &dyn Integrator,
+        settings: &RenderSettings,
+    ) -> Result<Framebuffer, RenderError> {
+        Ok(self.render_aovs(integrator, settings, &[])?.beauty)
+    }
 
-        if intersection.intersection.square_norm() > 1_000_000. {
-            return BLACK;
+    /// Render the scene along with the passes of the settings, see [`crate::aovs`]. The
+    /// beauty image is the same as the one of [`Scene::render`].
+    pub fn render_passes(&self, settings: &RenderSettings) -> Result<Render, RenderError> {
+        let integrator = integrators::from_settings(settings);
+        self.render_aovs(&*integrator, settings, &settings.aovs)
+    }
+
+    fn render_aovs(
+        &self,
+        integrator: &dyn Integrator,
+        settings: &RenderSettings,
+        aovs: &[Aov],
+    ) -> Result<Render, RenderError> {
+        if settings.progress {
+            println!("[*] Generating image...");
         }
-        if intersection.shape.get_material().specular && remaining_bounces > 0 {
-            ray.reflect(&intersection);
-            return self.get_color(ray, remaining_bounces - 1, diffused);
+        let (width, height) = settings.image_size;
+        let seed = settings.seed.unwrap_or_else(|| rand::rng().random());
+        let tiles = Tile::split(width, height);
+        let progress = Mutex::new(settings.progress.then(|| tqdm::pbar(Some(tiles.len()))));
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(settings.threads.unwrap_or(0))
+            .build()?;
+        // the beauty color of each pixel, followed by the values of the passes and the coverage
+        let rendered: Vec<Vec<Vec<Color>>> = pool.install(|| {
+            tiles
+                .par_iter()
+                .map(|tile| {
+                    let mut rng = tile.rng(seed);
+                    // the passes draw from their own generator, so that they leave the beauty
+                    // image unchanged
+                    let mut aov_rng = tile.rng(!seed);
+                    let values = tile
+                        .pixels()
+                        .map(|pixel| {
+                            let rngs = (&mut rng, &mut aov_rng);
+                            self.render_pixel(integrator, pixel, settings, aovs, rngs)
+                        })
+                        .collect();
+                    if let Some(pbar) = progress.lock().unwrap().as_mut() {
+                        pbar.update(1).ok();
+                    }
+                    values
+                })
+                .collect()
+        });
+
+        let values_count = if aovs.is_empty() { 1 } else { aovs.len() + 2 };
+        let mut framebuffers = vec![Framebuffer::new(width, height); values_count];
+        for (tile, values) in tiles.iter().zip(rendered) {
+            for ((x, y), values) in tile.pixels().zip(values) {
+                for (framebuffer, value) in framebuffers.iter_mut().zip(values) {
+                    framebuffer.put_pixel(x, y, value);
+                }
+            }
         }
-        if intersection.shape.get_material().refractive_index != 0. && remaining_bounces > 0 {
-            ray.refract(&intersection);
-            return self.get_color(ray, remaining_bounces - 1, diffused);
+        drop(progress);
+        if settings.progress {
+            println!("[+] Successfully generated image");
         }
-        let diffused_part = if diffused && remaining_bounces > 0 {
-            diffuse(ray, &intersection);
-            self.get_color(ray, remaining_bounces - 1, diffused)
+        let beauty = framebuffers.remove(0);
+        let coverage = if aovs.is_empty() {
+            None
         } else {
-            [0., 0., 0.]
+            framebuffers.pop()
         };
-        let color = if self.is_in_shadow(&intersection) {
-            BLACK
-        } else {
-            let light_vector = self.light.origin - intersection.intersection;
-            let light_vector_normalized = light_vector.normalize();
-            let light_distance = light_vector.square_norm();
-            let light_value = light_vector_normalized.dot(&intersection.normal)
-                * self.light.intensity as f32
-                / (2. * PI * light_distance);
-            let light_value = f32::max(light_value, 0.);
-            let mat = intersection.shape.get_material();
-            [
-                light_value * mat.color[0] as f32,
-                light_value * mat.color[1] as f32,
-                light_value * mat.color[2] as f32,
-            ]
-        };
-        [
-            color[0] + diffused_part[0],
-            color[1] + diffused_part[1],
-            color[2] + diffused_part[2],
-        ]
+        Ok(Render {
+            beauty,
+            aovs: aovs.iter().copied().zip(framebuffers).collect(),
+            coverage,
+        })
     }
 
-    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
-        self.shapes
-            .iter()
-            .filter_map(|shape| shape.get_intersection(&ray))
-            .min_by_key(|intersection| intersection.d.round() as u32)
+    /// Return the color of a pixel, followed by the values of the passes and by the fraction of
+    /// the samples hitting a surface if there are passes, averaged over the samples of the
+    /// integrator
+    fn render_pixel(
+        &self,
+        integrator: &dyn Integrator,
+        (x, y): (u32, u32),
+        settings: &RenderSettings,
+        aovs: &[Aov],
+        (rng, aov_rng): (&mut StdRng, &mut StdRng),
+    ) -> Vec<Color> {
+        let samples_count = integrator.samples_count();
+        let mut values = vec![Color::BLACK; aovs.len() + 1];
+        let mut hits_count = 0;
+        for sample in 0..samples_count {
+            // the pixels which the projection does not cover are black
+            let ray = self.camera.generate_ray(x, y, settings.image_size, rng);
+            if aovs.is_empty() {
+                if let Some(ray) = ray {
+                    values[0] += integrator.radiance(self, &ray, rng);
+                }
+                continue;
+            }
+            let radiance = match &ray {
+                Some(ray) => integrator.split_radiance(self, ray, rng),
+                None => (Color::BLACK, Color::BLACK),
+            };
+            values[0] += radiance.0 + radiance.1;
+            let hit = ray.as_ref().and_then(|ray| self.intersect_indexed(ray));
+            hits_count += u32::from(hit.is_some());
+            for (aov, value) in aovs.iter().zip(&mut values[1..]) {
+                if aov.is_averaged() || sample == 0 {
+                    *value += aov.value(self, hit.as_ref(), radiance, aov_rng);
+                }
+            }
+        }
+        values[0] = values[0] / samples_count as f32;
+        for (aov, value) in aovs.iter().zip(&mut values[1..]) {
+            if aov.is_averaged() {
+                *value = *value / samples_count as f32;
+            }
+        }
+        if !aovs.is_empty() {
+            values.push(Color::gray(hits_count as f32 / samples_count as f32));
+        }
+        values
+    }
+
+    /// Render the scene and its passes, and save them in the format given by the extension of
+    /// the path, see [`crate::output`]
+    pub fn generate_image<P: AsRef<Path>>(
+        &self,
+        settings: &RenderSettings,
+        path: P,
+    ) -> Result<(), RenderError> {
+        Ok(output::save_render(
+            &self.render_passes(settings)?,
+            path,
+            settings,
+        )?)
     }
 
-    fn is_in_shadow(&self, intersection: &Intersection) -> bool {
-        let v_light = self.light.origin - intersection.intersection;
-        let v_light_normalized = (self.light.origin - intersection.intersection).normalize();
-        let light_distance = v_light.square_norm();
+    /// Return the light reflected towards the direction `view` by a surface from the lights of
+    /// the scene. The glossy reflections of smooth surfaces are left to be traced.
+    pub fn get_direct_light(
+        &self,
+        intersection: &Intersection,
+        view: &Vector,
+        rng: &mut dyn RngCore,
+    ) -> Color {
+        let material = intersection.shape.get_material();
+        let normal = &intersection.normal;
+        let shading = material.shading(intersection.uv);
+        let diffuse = shading.diffuse(normal, view);
+        let glossy = shading
+            .microfacet
+            .filter(|microfacet| !microfacet.is_smooth());
+        let mut color = Color::BLACK;
+        for light in &self.lights {
+            let samples_count = light.samples_count();
+            for _ in 0..samples_count {
+                let sample = match light.illuminate(&intersection.intersection, rng) {
+                    Some(sample) => sample,
+                    None => continue,
+                };
+                let cos = sample.direction.dot(normal);
+                if cos <= 0. || self.is_in_shadow(intersection, &sample) {
+                    continue;
+                }
+                let mut reflectance = diffuse;
+                if glossy.is_some() {
+                    reflectance += shading.glossy(normal, view, &sample.direction);
+                }
+                color += sample.intensity * reflectance * (cos / samples_count as f32);
+            }
+        }
+        color
+    }
+
+    /// Return the closest intersection of the ray with the shapes of the scene, whose normal
+    /// is perturbed by the normal map of the material
+    pub fn intersect(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        self.intersect_indexed(ray)
+            .map(|(_, intersection)| intersection)
+    }
 
-        let tmp_ray = Ray {
-            origin: intersection.intersection,
-            direction: v_light_normalized,
+    /// Return the closest intersection of the ray with the shapes of the scene, with the index
+    /// of the shape hit
+    pub fn intersect_indexed(&self, ray: &Ray) -> Option<(usize, Intersection<'_>)> {
+        let mut closest = None;
+        let intersection = self.bvh.closest(ray, |index, ray| {
+            // the candidates are only kept when closer than the previous ones
+            let intersection = self.shapes[index]
+                .get_intersection(ray)
+                .filter(|intersection| intersection.d < ray.t_max);
+            if intersection.is_some() {
+                closest = Some(index);
+            }
+            intersection
+        })?;
+        let index = closest?;
+        let Some(normal_map) = &intersection.shape.get_material().normal_map else {
+            return Some((index, intersection));
         };
-        self.shapes
+        let normal = normal_map.perturb(&intersection);
+        // the side of the surface the ray is on is given by its geometric normal
+        let same_side =
+            (normal.dot(&ray.direction) < 0.) == (intersection.normal.dot(&ray.direction) < 0.);
+        if same_side {
+            Some((
+                index,
+                Intersection {
+                    normal,
+                    ..intersection
+                },
+            ))
+        } else {
+            Some((index, intersection))
+        }
+    }
+
+    /// Return the radiance of the closest light hit by the ray, if any
+    pub fn get_light_radiance(&self, ray: &Ray) -> Option<Color> {
+        self.lights
             .iter()
-            .filter_map(|shape| shape.get_intersection(&tmp_ray))
-            .any(|intersection| intersection.d.powi(2) < light_distance)
+            .filter_map(|light| light.get_intersection(ray))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, radiance)| radiance)
+    }
+
+    /// Return whether a shape stands between an intersection and a light
+    pub fn is_in_shadow(&self, intersection: &Intersection, sample: &LightSample) -> bool {
+        let ray = Ray {
+            t_max: sample.distance,
+            ..Ray::new(intersection.intersection, sample.direction)
+        };
+        self.bvh.any(&ray, |index| {
+            self.shapes[index].get_intersection(&ray).is_some()
+        })
     }
 }
 
-fn diffuse(ray: &mut Ray, intersection: &Intersection) {
-    ray.origin = intersection.intersection + intersection.normal * 0.0001;
-    ray.direction = {
-        let mut rng = rand::rng();
-        let intersect_dir_local = {
-            let r1: f32 = rng.random_range(0.0..1.);
-            let r2: f32 = rng.random_range(0.0..1.);
-            Vector {
-                x: (2. * PI * r1).cos() * (1. - r2).sqrt(),
-                y: (2. * PI * r1).sin() * (1. - r2).sqrt(),
-                z: r2.sqrt(),
-            }
+/// A rectangular area of the image, rendered as a unit by one thread
+struct Tile {
+    index: u64,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Tile {
+    /// Split an image into tiles of at most `TILE_SIZE` pixels wide and high
+    fn split(width: u32, height: u32) -> Vec<Tile> {
+        (0..height)
+            .step_by(TILE_SIZE as usize)
+            .flat_map(|y| (0..width).step_by(TILE_SIZE as usize).map(move |x| (x, y)))
+            .enumerate()
+            .map(|(index, (x, y))| Tile {
+                index: index as u64,
+                x,
+                y,
+                width: TILE_SIZE.min(width - x),
+                height: TILE_SIZE.min(height - y),
+            })
+            .collect()
+    }
+
+    /// The coordinates of the pixels of the tile, row by row
+    fn pixels(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+
+    /// The random number generator of the tile, derived from the seed of the render
+    fn rng(&self, seed: u64) -> StdRng {
+        StdRng::seed_from_u64(seed ^ self.index.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::example_scene;
+    use crate::materials::Material;
+    use crate::settings::IntegratorKind;
+    use crate::shapes::Sphere;
+
+    #[test]
+    fn test_split_tiles() {
+        let tiles = Tile::split(TILE_SIZE + 1, 2);
+        assert_eq!(tiles.len(), 2);
+        assert_eq!(tiles[1].x, TILE_SIZE);
+        assert_eq!(tiles[1].width, 1);
+        assert_eq!(
+            tiles
+                .iter()
+                .map(|tile| tile.pixels().count())
+                .sum::<usize>(),
+            2 * (TILE_SIZE as usize + 1)
+        );
+    }
+
+    #[test]
+    fn test_render_is_independent_of_threads() {
+        let scene = example_scene();
+        let settings = RenderSettings {
+            image_size: (TILE_SIZE + 8, 12),
+            diffused_samples_count: 2,
+            seed: Some(42),
+            progress: false,
+            ..RenderSettings::default()
         };
-        let tangent_1 = {
-            let random_vec = Vector {
-                x: rng.random_range(0.0..1.),
-                y: rng.random_range(0.0..1.),
-                z: rng.random_range(0.0..1.),
-            };
-            intersection.normal.cross(&random_vec)
+        let single_threaded = scene
+            .render(&RenderSettings {
+                threads: Some(1),
+                ..settings.clone()
+            })
+            .unwrap();
+        let multi_threaded = scene
+            .render(&RenderSettings {
+                threads: Some(4),
+                ..settings
+            })
+            .unwrap();
+        assert_eq!(single_threaded, multi_threaded);
+    }
+
+    #[test]
+    fn test_passes_leave_beauty_unchanged() {
+        let scene = example_scene();
+        let settings = RenderSettings {
+            image_size: (8, 6),
+            diffused_samples_count: 4,
+            seed: Some(7),
+            integrator: IntegratorKind::Path,
+            max_bounces: 3,
+            aovs: Aov::ALL.to_vec(),
+            progress: false,
+            ..RenderSettings::default()
+        };
+        let render = scene.render_passes(&settings).unwrap();
+        assert_eq!(render.beauty, scene.render(&settings).unwrap());
+        let aovs: Vec<Aov> = render.aovs.iter().map(|(aov, _)| *aov).collect();
+        assert_eq!(aovs, Aov::ALL);
+        // every ray hits the walls of the example scene
+        let coverage = render.coverage.as_ref().unwrap();
+        assert!(coverage.pixels().iter().all(|coverage| coverage.r == 1.));
+
+        let pass = |aov: Aov| &render.aovs.iter().find(|(a, _)| *a == aov).unwrap().1;
+        let (direct, indirect) = (pass(Aov::Direct), pass(Aov::Indirect));
+        for (i, beauty) in render.beauty.pixels().iter().enumerate() {
+            let sum = direct.pixels()[i] + indirect.pixels()[i];
+            assert!((sum.r - beauty.r).abs() <= 1e-4 * beauty.r.max(1.));
+        }
+        // the identifiers are not blended between the samples
+        for id in pass(Aov::ObjectId).pixels() {
+            assert_eq!(id.r, id.r.round());
+        }
+    }
+
+    #[test]
+    fn test_material_ids() {
+        let shared = Material::diffuse(Color::WHITE);
+        let emissive = Material {
+            emission: Color::WHITE,
+            ..shared.clone()
+        };
+        let shapes: Vec<Box<dyn Shape>> = [shared.clone(), emissive, shared]
+            .into_iter()
+            .map(|material| {
+                Box::new(Sphere {
+                    origin: Vector {
+                        x: 0.,
+                        y: 0.,
+                        z: 0.,
+                    },
+                    radius: 1.,
+                    material,
+                }) as Box<dyn Shape>
+            })
+            .collect();
+        let scene = example_scene();
+        let scene = Scene::new(shapes, scene.lights, scene.camera, scene.settings);
+        let ids: Vec<u32> = (0..3).map(|index| scene.material_id(index)).collect();
+        assert_eq!(ids, [1, 2, 1]);
+    }
+
+    fn sphere(z: f32, radius: f32) -> Box<dyn Shape> {
+        Box::new(Sphere {
+            origin: Vector { x: 0., y: 0., z },
+            radius,
+            material: Material::diffuse(Color::WHITE),
+        })
+    }
+
+    /// Two spheres touching at (0, 0, -0.2), the farthest one first
+    fn touching_spheres() -> Scene {
+        let scene = example_scene();
+        Scene::new(
+            vec![sphere(0., 0.2), sphere(-0.4, 0.2)],
+            scene.lights,
+            scene.camera,
+            scene.settings,
+        )
+    }
+
+    #[test]
+    fn test_intersect_touching_spheres() {
+        let scene = touching_spheres();
+        let forward = Vector {
+            x: 0.,
+            y: 0.,
+            z: 1.,
         };
-        let tangent_2 = intersection.normal.cross(&tangent_1);
+        // the spheres are hit at 9.6 and 10, which used to be rounded to the same distance
+        let ray = Ray::new(
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: -10.2,
+            },
+            forward,
+        );
+        let intersection = scene.intersect(&ray).unwrap();
+        assert!((intersection.d - 9.6).abs() < 1e-4);
+        assert!((intersection.intersection.z + 0.6).abs() < 1e-4);
 
-        (tangent_1 * intersect_dir_local.x
-            + tangent_2 * intersect_dir_local.y
-            + intersection.normal * intersect_dir_local.z)
-            .normalize()
-    };
+        // leaving the first sphere at the contact point hits the second one, not itself
+        let ray = Ray::new(
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: -0.2,
+            },
+            forward,
+        );
+        let intersection = scene.intersect(&ray).unwrap();
+        assert!((intersection.intersection.z - 0.2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_intersect_within_interval() {
+        let scene = touching_spheres();
+        let ray = Ray {
+            t_max: 9.,
+            ..Ray::new(
+                Vector {
+                    x: 0.,
+                    y: 0.,
+                    z: -10.2,
+                },
+                Vector {
+                    x: 0.,
+                    y: 0.,
+                    z: 1.,
+                },
+            )
+        };
+        assert!(scene.intersect(&ray).is_none());
+        let ray = Ray {
+            t_min: 9.7,
+            t_max: f32::INFINITY,
+            ..ray
+        };
+        let intersection = scene.intersect(&ray).unwrap();
+        assert!((intersection.intersection.z + 0.2).abs() < 1e-4);
+    }
 }
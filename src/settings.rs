@@ -0,0 +1,186 @@
+use crate::aovs::Aov;
+use crate::framebuffer::ToneMapping;
+use crate::output::ExrPrecision;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The parameters of a render.
+///
+/// A scene file may provide them in a `[settings]` table, any missing field taking its
+/// default value:
+///
+/// ```toml
+/// [settings]
+/// image_size = [640, 480]
+/// max_bounces = 1
+/// diffused = true
+/// diffused_samples_count = 500
+/// seed = 42
+/// integrator = "whitted"
+/// exposure = 0.5
+/// tone_mapping = "aces"
+/// exr_precision = "full"
+/// aovs = ["depth", "normal", "albedo"]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderSettings {
+    /// width and height of the image, in pixels
+    pub image_size: (u32, u32),
+    pub max_bounces: u8,
+    /// whether to compute the indirect diffused light
+    pub diffused: bool,
+    /// number of rays traced per pixel to estimate the diffused light, or of paths and
+    /// occlusion rays with the other integrators
+    pub diffused_samples_count: u32,
+    /// seed of the random number generator, for reproducible renders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// the algorithm computing the light seen along the rays
+    pub integrator: IntegratorKind,
+    /// distance within which surfaces occlude each other, for ambient occlusion
+    pub occlusion_distance: f32,
+    /// the brightness of the image, in stops: each one doubles the light
+    pub exposure: f32,
+    /// how the colors brighter than white are displayed, see [`ToneMapping`]
+    pub tone_mapping: ToneMapping,
+    /// the precision of the channels of the OpenEXR images, see [`crate::output`]
+    pub exr_precision: ExrPrecision,
+    /// the passes rendered along with the image, see [`crate::aovs`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aovs: Vec<Aov>,
+    /// number of threads to render with, defaults to the number of cores
+    #[serde(skip)]
+    pub threads: Option<usize>,
+    /// whether to print progress to the terminal
+    #[serde(skip)]
+    pub progress: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            image_size: (640, 480),
+            max_bounces: 1,
+            diffused: true,
+            diffused_samples_count: 500,
+            seed: None,
+            integrator: IntegratorKind::Whitted,
+            occlusion_distance: 10.,
+            exposure: 0.,
+            tone_mapping: ToneMapping::Clamp,
+            exr_precision: ExrPrecision::Half,
+            aovs: Vec::new(),
+            threads: None,
+            progress: true,
+        }
+    }
+}
+
+/// The integrators which can be selected by name, see [`crate::integrators`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegratorKind {
+    /// Reflections, refractions and direct lighting. When `diffused` is set, a single diffuse
+    /// bounce is added, averaged over `diffused_samples_count` rays. Emissive materials are
+    /// visible but do not light the scene.
+    Whitted,
+    /// Unbiased path tracing of up to `max_bounces` bounces, averaged over
+    /// `diffused_samples_count` paths per pixel
+    Path,
+    /// The fraction of the hemisphere above the surfaces which is not occluded within
+    /// `occlusion_distance`, estimated with `diffused_samples_count` rays per pixel
+    AmbientOcclusion,
+    /// The normals of the surfaces, for debugging
+    Normals,
+}
+
+impl IntegratorKind {
+    pub const ALL: [IntegratorKind; 4] = [
+        IntegratorKind::Whitted,
+        IntegratorKind::Path,
+        IntegratorKind::AmbientOcclusion,
+        IntegratorKind::Normals,
+    ];
+
+    /// The name of the integrator, in scene files and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegratorKind::Whitted => "whitted",
+            IntegratorKind::Path => "path",
+            IntegratorKind::AmbientOcclusion => "ambient_occlusion",
+            IntegratorKind::Normals => "normals",
+        }
+    }
+}
+
+impl fmt::Display for IntegratorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for IntegratorKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(IntegratorKind::name).collect();
+                format!(
+                    "unknown integrator `{name}`, expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_settings() {
+        let settings: RenderSettings = toml::from_str("image_size = [64, 48]").unwrap();
+        assert_eq!(
+            settings,
+            RenderSettings {
+                image_size: (64, 48),
+                ..RenderSettings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let settings = RenderSettings {
+            diffused: false,
+            seed: Some(42),
+            integrator: IntegratorKind::Path,
+            exposure: -1.5,
+            tone_mapping: ToneMapping::Reinhard,
+            exr_precision: ExrPrecision::Full,
+            aovs: vec![Aov::Depth, Aov::ObjectId],
+            ..RenderSettings::default()
+        };
+        let serialized = toml::to_string(&settings).unwrap();
+        assert_eq!(
+            toml::from_str::<RenderSettings>(&serialized).unwrap(),
+            settings
+        );
+    }
+
+    #[test]
+    fn test_integrator_names() {
+        for kind in IntegratorKind::ALL {
+            assert_eq!(kind.name().parse(), Ok(kind));
+            let settings: RenderSettings =
+                toml::from_str(&format!("integrator = \"{kind}\"")).unwrap();
+            assert_eq!(settings.integrator, kind);
+        }
+        assert!("photon".parse::<IntegratorKind>().is_err());
+    }
+}
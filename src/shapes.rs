@@ -1,32 +1,63 @@
 use crate::materials::Material;
+use crate::utils::aabb::Aabb;
 use crate::utils::intersection::Intersection;
 use crate::utils::ray::Ray;
 use crate::utils::vector::Vector;
+use std::f32::consts::PI;
 use std::fmt::Debug;
 
-pub trait Shape {
+mod plane;
+mod transformed;
+mod triangle;
+
+pub use plane::{Disk, Plane, Quad};
+pub use transformed::Transformed;
+pub use triangle::{MeshError, Triangle, TriangleMesh};
+
+pub trait Shape: Send + Sync {
     /// Return the intersection between the shape and a ray
-    fn get_intersection(&self, ray: &Ray) -> Option<Intersection> {
-        let poly = self.get_poly(ray);
-        let distance = solve_poly(poly.0, poly.1, poly.2);
-        match distance {
-            Some(d) => {
-                let intersection = ray.direction * d + ray.origin;
-                // see https://github.com/thomasperrot/python_graphique/blob/master/decors/Scene.py#L141C57-L141C58. Maybe todo
-                Some(self.intersection(d, intersection))
-            }
-            None => None,
-        }
-    }
+    fn get_intersection(&self, ray: &Ray) -> Option<Intersection<'_>>;
+
+    fn get_material(&self) -> &Material;
 
+    /// Return the bounding box of the shape, which is infinite for unbounded shapes
+    fn bounds(&self) -> Aabb;
+}
+
+/// A shape whose intersections with a ray are the roots of a quadratic polynomial
+pub trait Quadric: Shape {
     /// Return the normal vector to the shape at a given point
     fn get_normal(&self, v: &Vector) -> Vector;
 
-    fn get_material(&self) -> Material;
+    /// Return the texture coordinates of a point of the shape, given its normal
+    fn get_uv(&self, v: &Vector, normal: &Vector) -> [f32; 2];
+
+    /// Return the derivatives of a point of the shape along its texture coordinates
+    fn get_tangents(&self, v: &Vector, normal: &Vector) -> (Vector, Vector);
 
+    /// Return the coefficients of the polynomial whose roots are the distances from the
+    /// origin of the ray to its intersections with the shape
     fn get_poly(&self, ray: &Ray) -> (f32, f32, f32);
+}
 
-    fn intersection(&self, d: f32, inter: Vector) -> Intersection;
+/// Return the intersection between a quadric and a ray
+fn get_quadric_intersection<'a, S: Quadric>(shape: &'a S, ray: &Ray) -> Option<Intersection<'a>> {
+    let poly = shape.get_poly(ray);
+    let d = solve_poly(poly.0, poly.1, poly.2, ray)?;
+    let intersection = ray.direction * d + ray.origin;
+    // see https://github.com/thomasperrot/python_graphique/blob/master/decors/Scene.py#L141
+    // Maybe todo
+    let normal = shape.get_normal(&intersection);
+    let (dpdu, dpdv) = shape.get_tangents(&intersection, &normal);
+    Some(Intersection {
+        d,
+        intersection,
+        normal,
+        uv: shape.get_uv(&intersection, &normal),
+        dpdu,
+        dpdv,
+        shape,
+    })
 }
 
 impl Debug for dyn Shape {
@@ -42,12 +73,62 @@ pub struct Sphere {
 }
 
 impl Shape for Sphere {
+    fn get_intersection(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        get_quadric_intersection(self, ray)
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> Aabb {
+        let radius = Vector {
+            x: self.radius,
+            y: self.radius,
+            z: self.radius,
+        };
+        Aabb {
+            min: self.origin - radius,
+            max: self.origin + radius,
+        }
+    }
+}
+
+impl Quadric for Sphere {
     fn get_normal(&self, v: &Vector) -> Vector {
         (*v - self.origin).normalize()
     }
 
-    fn get_material(&self) -> Material {
-        self.material
+    /// The longitude and latitude of the point, the poles being on the y axis
+    fn get_uv(&self, _v: &Vector, normal: &Vector) -> [f32; 2] {
+        [
+            0.5 + normal.z.atan2(normal.x) / (2. * PI),
+            0.5 + normal.y.clamp(-1., 1.).asin() / PI,
+        ]
+    }
+
+    /// Along the parallels and the meridians, vanishing at the poles
+    fn get_tangents(&self, _v: &Vector, normal: &Vector) -> (Vector, Vector) {
+        let cos_latitude = (normal.x.powi(2) + normal.z.powi(2)).sqrt();
+        let dpdu = Vector {
+            x: -normal.z,
+            y: 0.,
+            z: normal.x,
+        } * (2. * PI * self.radius);
+        let dpdv = if cos_latitude > 0. {
+            Vector {
+                x: -normal.y * normal.x / cos_latitude,
+                y: cos_latitude,
+                z: -normal.y * normal.z / cos_latitude,
+            } * (PI * self.radius)
+        } else {
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: 0.,
+            }
+        };
+        (dpdu, dpdv)
     }
 
     fn get_poly(&self, ray: &Ray) -> (f32, f32, f32) {
@@ -56,14 +137,6 @@ impl Shape for Sphere {
         let c = (ray.origin - self.origin).square_norm() - self.radius.powi(2);
         (a, b, c)
     }
-    fn intersection(&self, d: f32, inter: Vector) -> Intersection {
-        Intersection {
-            d,
-            intersection: inter,
-            normal: self.get_normal(&inter),
-            shape: self,
-        }
-    }
 }
 
 /// An hyperboloid structure
@@ -76,6 +149,20 @@ pub struct Hyperboloid {
 }
 
 impl Shape for Hyperboloid {
+    fn get_intersection(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        get_quadric_intersection(self, ray)
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::INFINITE
+    }
+}
+
+impl Quadric for Hyperboloid {
     fn get_poly(&self, ray: &Ray) -> (f32, f32, f32) {
         let a = (ray.direction.x / self.coefficients.0).powi(2)
             - (ray.direction.y / self.coefficients.1).powi(2)
@@ -90,6 +177,41 @@ impl Shape for Hyperboloid {
             + 1.;
         (a, b, c)
     }
+
+    /// The angle around the y axis and the height of the point
+    fn get_uv(&self, v: &Vector, _normal: &Vector) -> [f32; 2] {
+        [
+            0.5 + (v.z - self.origin.z).atan2(v.x - self.origin.x) / (2. * PI),
+            v.y - self.origin.y,
+        ]
+    }
+
+    /// Around the y axis, and along the sheet at a constant angle
+    fn get_tangents(&self, v: &Vector, _normal: &Vector) -> (Vector, Vector) {
+        let (x, y, z) = (
+            v.x - self.origin.x,
+            v.y - self.origin.y,
+            v.z - self.origin.z,
+        );
+        let dpdu = Vector { x: -z, y: 0., z: x } * (2. * PI);
+        // the distance to the axis grows like √(y² / b² - 1)
+        let spread = y / (y.powi(2) - self.coefficients.1.powi(2));
+        let dpdv = if spread.is_finite() {
+            Vector {
+                x: x * spread,
+                y: 1.,
+                z: z * spread,
+            }
+        } else {
+            Vector {
+                x: 0.,
+                y: 1.,
+                z: 0.,
+            }
+        };
+        (dpdu, dpdv)
+    }
+
     fn get_normal(&self, v: &Vector) -> Vector {
         Vector {
             x: (v.x - self.origin.x) / (self.coefficients.0.powi(2)),
@@ -98,31 +220,20 @@ impl Shape for Hyperboloid {
         }
         .normalize()
     }
-
-    fn get_material(&self) -> Material {
-        self.material
-    }
-    fn intersection(&self, d: f32, inter: Vector) -> Intersection {
-        Intersection {
-            d,
-            intersection: inter,
-            normal: self.get_normal(&inter),
-            shape: self,
-        }
-    }
 }
 
-fn solve_poly(a: f32, b: f32, c: f32) -> Option<f32> {
+/// Return the smallest root of the polynomial in the interval of the ray
+fn solve_poly(a: f32, b: f32, c: f32, ray: &Ray) -> Option<f32> {
     let delta = b * b - 4. * a * c;
     if delta > 0. {
-        let tmin = (-b - delta.sqrt()) / (2.0 * a);
-        let tmax = (-b + delta.sqrt()) / (2.0 * a);
-        if tmax > 0. {
-            if tmin > 0. {
-                Some(tmin)
-            } else {
-                Some(tmax)
-            }
+        let t1 = (-b - delta.sqrt()) / (2.0 * a);
+        let t2 = (-b + delta.sqrt()) / (2.0 * a);
+        // `a` may be negative, in which case the roots are swapped
+        let (tmin, tmax) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+        if ray.contains(tmin) {
+            Some(tmin)
+        } else if ray.contains(tmax) {
+            Some(tmax)
         } else {
             None
         }
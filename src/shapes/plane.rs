@@ -0,0 +1,230 @@
+use crate::materials::Material;
+use crate::shapes::Shape;
+use crate::utils::aabb::Aabb;
+use crate::utils::intersection::Intersection;
+use crate::utils::ray::Ray;
+use crate::utils::vector::Vector;
+
+/// Rays closer than this to being parallel to a plane do not intersect it
+const EPSILON: f32 = 1e-7;
+
+/// An infinite plane
+pub struct Plane {
+    /// any point of the plane
+    pub origin: Vector,
+    /// the normal of the front face of the plane, which must be normalized
+    pub normal: Vector,
+    pub material: Material,
+}
+
+impl Shape for Plane {
+    fn get_intersection(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        let d = intersect_plane(ray, self.origin, self.normal)?;
+        let intersection = ray.direction * d + ray.origin;
+        // the plane is tiled by the texture, along two directions of the plane
+        let (tangent_1, tangent_2) = self.normal.tangents();
+        let p = intersection - self.origin;
+        Some(Intersection {
+            d,
+            intersection,
+            normal: self.normal,
+            uv: [p.dot(&tangent_1), p.dot(&tangent_2)],
+            dpdu: tangent_1,
+            dpdv: tangent_2,
+            shape: self,
+        })
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::INFINITE
+    }
+}
+
+/// A parallelogram, spanned by two edges from one of its corners. The normal of its front
+/// face is `edge_1 × edge_2`.
+pub struct Quad {
+    /// a corner of the parallelogram
+    pub origin: Vector,
+    pub edge_1: Vector,
+    pub edge_2: Vector,
+    pub material: Material,
+}
+
+impl Quad {
+    pub fn get_normal(&self) -> Vector {
+        self.edge_1.cross(&self.edge_2).normalize()
+    }
+}
+
+impl Shape for Quad {
+    fn get_intersection(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        let n = self.edge_1.cross(&self.edge_2);
+        let normal = n.normalize();
+        let d = intersect_plane(ray, self.origin, normal)?;
+        let intersection = ray.direction * d + ray.origin;
+
+        // the coordinates of the intersection in the basis of the edges
+        let w = n * (1. / n.square_norm());
+        let p = intersection - self.origin;
+        let alpha = w.dot(&p.cross(&self.edge_2));
+        let beta = w.dot(&self.edge_1.cross(&p));
+        if !(0. ..=1.).contains(&alpha) || !(0. ..=1.).contains(&beta) {
+            return None;
+        }
+        Some(Intersection {
+            d,
+            intersection,
+            normal,
+            uv: [alpha, beta],
+            dpdu: self.edge_1,
+            dpdv: self.edge_2,
+            shape: self,
+        })
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points([
+            self.origin,
+            self.origin + self.edge_1,
+            self.origin + self.edge_2,
+            self.origin + self.edge_1 + self.edge_2,
+        ])
+    }
+}
+
+/// A disk
+pub struct Disk {
+    /// the center of the disk
+    pub origin: Vector,
+    /// the normal of the front face of the disk, which must be normalized
+    pub normal: Vector,
+    pub radius: f32,
+    pub material: Material,
+}
+
+impl Shape for Disk {
+    fn get_intersection(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        let d = intersect_plane(ray, self.origin, self.normal)?;
+        let intersection = ray.direction * d + ray.origin;
+        let p = intersection - self.origin;
+        if p.square_norm() > self.radius.powi(2) {
+            return None;
+        }
+        // the texture is stretched over the square enclosing the disk
+        let (tangent_1, tangent_2) = self.normal.tangents();
+        let uv = [tangent_1, tangent_2].map(|tangent| 0.5 + p.dot(&tangent) / (2. * self.radius));
+        Some(Intersection {
+            d,
+            intersection,
+            normal: self.normal,
+            uv,
+            dpdu: tangent_1 * (2. * self.radius),
+            dpdv: tangent_2 * (2. * self.radius),
+            shape: self,
+        })
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> Aabb {
+        // the extent of the disk along an axis is the radius scaled by the sine of the angle
+        // between the axis and the normal
+        let extent = Vector {
+            x: self.radius * (1. - self.normal.x.powi(2)).max(0.).sqrt(),
+            y: self.radius * (1. - self.normal.y.powi(2)).max(0.).sqrt(),
+            z: self.radius * (1. - self.normal.z.powi(2)).max(0.).sqrt(),
+        };
+        Aabb {
+            min: self.origin - extent,
+            max: self.origin + extent,
+        }
+    }
+}
+
+/// Return the distance from the origin of the ray to a plane
+fn intersect_plane(ray: &Ray, origin: Vector, normal: Vector) -> Option<f32> {
+    let denominator = ray.direction.dot(&normal);
+    if denominator.abs() < EPSILON {
+        return None;
+    }
+    let d = (origin - ray.origin).dot(&normal) / denominator;
+    if ray.contains(d) {
+        Some(d)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::color::Color;
+
+    fn material() -> Material {
+        Material::diffuse(Color::WHITE)
+    }
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    fn ray_towards(x: f32, y: f32) -> Ray {
+        Ray::new(vector(x, y, 5.), vector(0., 0., -1.))
+    }
+
+    #[test]
+    fn test_plane() {
+        let plane = Plane {
+            origin: vector(0., 0., 1.),
+            normal: vector(0., 0., 1.),
+            material: material(),
+        };
+        let intersection = plane.get_intersection(&ray_towards(100., -100.)).unwrap();
+        assert_eq!(intersection.d, 4.);
+        assert_eq!(intersection.intersection, vector(100., -100., 1.));
+        let parallel = Ray::new(vector(0., 0., 5.), vector(1., 0., 0.));
+        assert!(plane.get_intersection(&parallel).is_none());
+    }
+
+    #[test]
+    fn test_quad() {
+        let quad = Quad {
+            origin: vector(0., 0., 0.),
+            edge_1: vector(2., 0., 0.),
+            edge_2: vector(1., 1., 0.),
+            material: material(),
+        };
+        assert_eq!(quad.get_normal(), vector(0., 0., 1.));
+        let intersection = quad.get_intersection(&ray_towards(2.5, 0.5)).unwrap();
+        // the texture coordinates are the fractions of the edges
+        assert_eq!(intersection.uv, [1., 0.5]);
+        assert!(quad.get_intersection(&ray_towards(0.25, 0.5)).is_none());
+        assert_eq!(quad.bounds().max, vector(3., 1., 0.));
+    }
+
+    #[test]
+    fn test_disk() {
+        let disk = Disk {
+            origin: vector(0., 0., 0.),
+            normal: vector(0., 0., 1.),
+            radius: 1.,
+            material: material(),
+        };
+        let intersection = disk.get_intersection(&ray_towards(0.5, 0.5)).unwrap();
+        assert!(intersection.uv.iter().all(|c| (0. ..=1.).contains(c)));
+        let center = disk.get_intersection(&ray_towards(0., 0.)).unwrap();
+        assert_eq!(center.uv, [0.5, 0.5]);
+        assert!(disk.get_intersection(&ray_towards(0.75, 0.75)).is_none());
+        assert_eq!(disk.bounds().min, vector(-1., -1., 0.));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::materials::Material;
+use crate::shapes::Shape;
+use crate::utils::aabb::Aabb;
+use crate::utils::intersection::Intersection;
+use crate::utils::matrix::Matrix;
+use crate::utils::ray::Ray;
+use crate::utils::vector::Vector;
+use std::sync::Arc;
+
+/// A shape moved by an affine transform. The shape is shared, so that it can be instanced
+/// several times with different transforms without duplicating its data.
+pub struct Transformed<S: Shape + ?Sized> {
+    shape: Arc<S>,
+    /// the transform from the space of the shape to the world space
+    transform: Matrix,
+    inverse: Matrix,
+    /// the transpose of `inverse`, which transforms the normals to the world space
+    normal_transform: Matrix,
+    bounds: Aabb,
+}
+
+impl<S: Shape + ?Sized> Transformed<S> {
+    /// Return `None` if the transform is not invertible
+    pub fn new(shape: Arc<S>, transform: Matrix) -> Option<Self> {
+        let inverse = transform.inverse()?;
+        let bounds = shape.bounds();
+        let bounds = if bounds.is_finite() {
+            let corners = [bounds.min.x, bounds.max.x].into_iter().flat_map(|x| {
+                [bounds.min.y, bounds.max.y].into_iter().flat_map(move |y| {
+                    [bounds.min.z, bounds.max.z]
+                        .into_iter()
+                        .map(move |z| transform.transform_point(&Vector { x, y, z }))
+                })
+            });
+            Aabb::from_points(corners)
+        } else {
+            Aabb::INFINITE
+        };
+        Some(Self {
+            shape,
+            transform,
+            inverse,
+            normal_transform: inverse.transpose(),
+            bounds,
+        })
+    }
+
+    pub fn shape(&self) -> &Arc<S> {
+        &self.shape
+    }
+
+    pub fn transform(&self) -> &Matrix {
+        &self.transform
+    }
+}
+
+impl<S: Shape + ?Sized> Shape for Transformed<S> {
+    fn get_intersection(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        // the shapes expect normalized directions, so distances are scaled in their space
+        let direction = self.inverse.transform_vector(&ray.direction);
+        let scale = direction.square_norm().sqrt();
+        let local_ray = Ray {
+            origin: self.inverse.transform_point(&ray.origin),
+            direction: direction * (1. / scale),
+            t_min: ray.t_min * scale,
+            t_max: ray.t_max * scale,
+        };
+        let intersection = self.shape.get_intersection(&local_ray)?;
+        let d = intersection.d / scale;
+        Some(Intersection {
+            d,
+            intersection: ray.direction * d + ray.origin,
+            normal: self
+                .normal_transform
+                .transform_vector(&intersection.normal)
+                .normalize(),
+            uv: intersection.uv,
+            dpdu: self.transform.transform_vector(&intersection.dpdu),
+            dpdv: self.transform.transform_vector(&intersection.dpdv),
+            shape: intersection.shape,
+        })
+    }
+
+    fn get_material(&self) -> &Material {
+        self.shape.get_material()
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::Sphere;
+    use crate::utils::color::Color;
+    use std::f32::consts::PI;
+    use std::sync::Arc;
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    fn unit_sphere() -> Arc<Sphere> {
+        Arc::new(Sphere {
+            origin: vector(0., 0., 0.),
+            radius: 1.,
+            material: Material::diffuse(Color::WHITE),
+        })
+    }
+
+    #[test]
+    fn test_ellipsoid() {
+        let ellipsoid = Transformed::new(
+            unit_sphere(),
+            Matrix::translation(vector(0., 0., 10.)) * Matrix::scaling(vector(1., 1., 4.)),
+        )
+        .unwrap();
+        assert_eq!(ellipsoid.bounds().min, vector(-1., -1., 6.));
+
+        let ray = Ray::new(vector(0., 0., 0.), vector(0., 0., 1.));
+        let intersection = ellipsoid.get_intersection(&ray).unwrap();
+        assert!((intersection.d - 6.).abs() < 1e-5);
+        assert!((intersection.normal - vector(0., 0., -1.)).square_norm() < 1e-10);
+        // the texture coordinates are those of the sphere, on its equator
+        assert!(
+            (intersection.uv[0] - 0.25).abs() < 1e-5 && (intersection.uv[1] - 0.5).abs() < 1e-5
+        );
+
+        // the normal of a stretched sphere is not the stretched normal of the sphere
+        let ray = Ray::new(vector(0., 0.5, 0.), vector(0., 0., 1.));
+        let normal = ellipsoid.get_intersection(&ray).unwrap().normal;
+        assert!(normal.y > 0.9 && normal.z < 0.);
+
+        let short_ray = Ray { t_max: 5., ..ray };
+        assert!(ellipsoid.get_intersection(&short_ray).is_none());
+    }
+
+    #[test]
+    fn test_instances() {
+        let sphere = unit_sphere();
+        let instances: Vec<Transformed<Sphere>> = (0..3)
+            .map(|i| {
+                let transform = Matrix::rotation_y(i as f32 * PI / 2.)
+                    * Matrix::translation(vector(0., 0., 5.));
+                Transformed::new(Arc::clone(&sphere), transform).unwrap()
+            })
+            .collect();
+        assert_eq!(Arc::strong_count(&sphere), 4);
+
+        let ray = Ray::new(vector(10., 0., 0.), vector(-1., 0., 0.));
+        assert!(instances[0].get_intersection(&ray).is_none());
+        let intersection = instances[1].get_intersection(&ray).unwrap();
+        assert!((intersection.d - 4.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_singular_transform() {
+        let flat = Matrix::scaling(vector(1., 0., 1.));
+        assert!(Transformed::new(unit_sphere(), flat).is_none());
+    }
+}
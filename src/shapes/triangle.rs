@@ -0,0 +1,368 @@
+use crate::bvh::Bvh;
+use crate::materials::Material;
+use crate::shapes::Shape;
+use crate::utils::aabb::Aabb;
+use crate::utils::intersection::Intersection;
+use crate::utils::ray::Ray;
+use crate::utils::vector::Vector;
+use std::fmt;
+
+/// Rays closer than this to the plane of a triangle are considered parallel to it
+const EPSILON: f32 = 1e-7;
+
+/// A triangle, whose vertices are given counterclockwise when looking at its front face
+pub struct Triangle {
+    pub vertices: [Vector; 3],
+    /// the normals at each vertex, interpolated for smooth shading. Without them, the
+    /// triangle is flat shaded.
+    pub normals: Option<[Vector; 3]>,
+    /// the texture coordinates at each vertex
+    pub uvs: Option<[[f32; 2]; 3]>,
+    pub material: Material,
+}
+
+impl Triangle {
+    /// Return the texture coordinates at a point given by its barycentric coordinates
+    pub fn get_uv(&self, u: f32, v: f32) -> [f32; 2] {
+        match self.uvs {
+            Some(uvs) => interpolate_uv(uvs, u, v),
+            None => [u, v],
+        }
+    }
+}
+
+impl Shape for Triangle {
+    fn get_intersection(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        let (d, u, v) = intersect_triangle(ray, self.vertices)?;
+        let normal = match self.normals {
+            Some(normals) => interpolate_normal(normals, u, v),
+            None => flat_normal(self.vertices),
+        };
+        let (dpdu, dpdv) = uv_derivatives(self.vertices, self.uvs);
+        Some(Intersection {
+            d,
+            intersection: ray.direction * d + ray.origin,
+            normal,
+            uv: self.get_uv(u, v),
+            dpdu,
+            dpdv,
+            shape: self,
+        })
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(self.vertices)
+    }
+}
+
+/// An error raised when building an invalid triangle mesh
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshError {
+    /// A triangle references a vertex which does not exist
+    IndexOutOfBounds { triangle: usize, index: usize },
+    /// The normals or texture coordinates are not given for every vertex
+    AttributeCount {
+        attribute: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::IndexOutOfBounds { triangle, index } => write!(
+                f,
+                "triangle {triangle} references vertex {index}, which does not exist"
+            ),
+            MeshError::AttributeCount {
+                attribute,
+                expected,
+                actual,
+            } => write!(f, "expected {expected} {attribute}, got {actual}"),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+/// A mesh of triangles sharing their vertices. The normals and texture coordinates, when
+/// given, are indexed like the positions.
+pub struct TriangleMesh {
+    positions: Vec<Vector>,
+    normals: Option<Vec<Vector>>,
+    uvs: Option<Vec<[f32; 2]>>,
+    /// the indices of the vertices of each triangle, counterclockwise
+    triangles: Vec<[usize; 3]>,
+    material: Material,
+    /// the hierarchy of the bounding boxes of `triangles`
+    bvh: Bvh,
+    bounds: Aabb,
+}
+
+impl TriangleMesh {
+    pub fn new(
+        positions: Vec<Vector>,
+        normals: Option<Vec<Vector>>,
+        uvs: Option<Vec<[f32; 2]>>,
+        triangles: Vec<[usize; 3]>,
+        material: Material,
+    ) -> Result<Self, MeshError> {
+        for (attribute, actual) in [
+            ("normals", normals.as_ref().map(Vec::len)),
+            ("texture coordinates", uvs.as_ref().map(Vec::len)),
+        ] {
+            match actual {
+                Some(actual) if actual != positions.len() => {
+                    return Err(MeshError::AttributeCount {
+                        attribute,
+                        expected: positions.len(),
+                        actual,
+                    })
+                }
+                _ => {}
+            }
+        }
+        for (triangle, indices) in triangles.iter().enumerate() {
+            if let Some(&index) = indices.iter().find(|&&index| index >= positions.len()) {
+                return Err(MeshError::IndexOutOfBounds { triangle, index });
+            }
+        }
+
+        let triangle_bounds: Vec<Aabb> = triangles
+            .iter()
+            .map(|indices| Aabb::from_points(indices.map(|index| positions[index])))
+            .collect();
+        let bounds = triangle_bounds
+            .iter()
+            .fold(Aabb::EMPTY, |aabb, triangle| aabb.union(triangle));
+        Ok(Self {
+            bvh: Bvh::build(&triangle_bounds),
+            bounds,
+            positions,
+            normals,
+            uvs,
+            triangles,
+            material,
+        })
+    }
+
+    pub fn triangles_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Return the texture coordinates at a point of a triangle, given by its barycentric
+    /// coordinates
+    pub fn get_uv(&self, triangle: usize, u: f32, v: f32) -> [f32; 2] {
+        match &self.uvs {
+            Some(uvs) => interpolate_uv(self.triangles[triangle].map(|index| uvs[index]), u, v),
+            None => [u, v],
+        }
+    }
+
+    fn get_triangle_intersection(&self, triangle: usize, ray: &Ray) -> Option<Intersection<'_>> {
+        let indices = self.triangles[triangle];
+        let (d, u, v) = intersect_triangle(ray, indices.map(|index| self.positions[index]))?;
+        let normal = match &self.normals {
+            Some(normals) => interpolate_normal(indices.map(|index| normals[index]), u, v),
+            None => flat_normal(indices.map(|index| self.positions[index])),
+        };
+        let (dpdu, dpdv) = uv_derivatives(
+            indices.map(|index| self.positions[index]),
+            self.uvs.as_ref().map(|uvs| indices.map(|index| uvs[index])),
+        );
+        Some(Intersection {
+            d,
+            intersection: ray.direction * d + ray.origin,
+            normal,
+            uv: self.get_uv(triangle, u, v),
+            dpdu,
+            dpdv,
+            shape: self,
+        })
+    }
+}
+
+impl Shape for TriangleMesh {
+    fn get_intersection(&self, ray: &Ray) -> Option<Intersection<'_>> {
+        self.bvh.closest(ray, |triangle, ray| {
+            self.get_triangle_intersection(triangle, ray)
+        })
+    }
+
+    fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+/// Return the distance from the origin of the ray to the triangle, and the barycentric
+/// coordinates of the intersection, using the Möller–Trumbore algorithm
+fn intersect_triangle(ray: &Ray, vertices: [Vector; 3]) -> Option<(f32, f32, f32)> {
+    let edge_1 = vertices[1] - vertices[0];
+    let edge_2 = vertices[2] - vertices[0];
+    let p = ray.direction.cross(&edge_2);
+    let determinant = edge_1.dot(&p);
+    if determinant.abs() < EPSILON {
+        return None;
+    }
+    let inverse_determinant = 1. / determinant;
+    let t = ray.origin - vertices[0];
+    let u = t.dot(&p) * inverse_determinant;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+    let q = t.cross(&edge_1);
+    let v = ray.direction.dot(&q) * inverse_determinant;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+    let d = edge_2.dot(&q) * inverse_determinant;
+    if ray.contains(d) {
+        Some((d, u, v))
+    } else {
+        None
+    }
+}
+
+fn flat_normal(vertices: [Vector; 3]) -> Vector {
+    (vertices[1] - vertices[0])
+        .cross(&(vertices[2] - vertices[0]))
+        .normalize()
+}
+
+fn interpolate_normal(normals: [Vector; 3], u: f32, v: f32) -> Vector {
+    (normals[0] * (1. - u - v) + normals[1] * u + normals[2] * v).normalize()
+}
+
+/// Return the derivatives of the position along the texture coordinates, which are constant
+/// over a triangle. Without texture coordinates, the barycentric coordinates are used.
+fn uv_derivatives(vertices: [Vector; 3], uvs: Option<[[f32; 2]; 3]>) -> (Vector, Vector) {
+    let uvs = uvs.unwrap_or([[0., 0.], [1., 0.], [0., 1.]]);
+    let (edge_1, edge_2) = (vertices[1] - vertices[0], vertices[2] - vertices[0]);
+    let (du_1, dv_1) = (uvs[1][0] - uvs[0][0], uvs[1][1] - uvs[0][1]);
+    let (du_2, dv_2) = (uvs[2][0] - uvs[0][0], uvs[2][1] - uvs[0][1]);
+    let determinant = du_1 * dv_2 - dv_1 * du_2;
+    if determinant.abs() < EPSILON {
+        // the texture coordinates do not span the triangle
+        return flat_normal(vertices).tangents();
+    }
+    let inverse_determinant = 1. / determinant;
+    (
+        (edge_1 * dv_2 - edge_2 * dv_1) * inverse_determinant,
+        (edge_2 * du_1 - edge_1 * du_2) * inverse_determinant,
+    )
+}
+
+fn interpolate_uv(uvs: [[f32; 2]; 3], u: f32, v: f32) -> [f32; 2] {
+    let w = 1. - u - v;
+    [
+        uvs[0][0] * w + uvs[1][0] * u + uvs[2][0] * v,
+        uvs[0][1] * w + uvs[1][1] * u + uvs[2][1] * v,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::color::Color;
+
+    fn material() -> Material {
+        Material::diffuse(Color::WHITE)
+    }
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    fn ray_towards(x: f32, y: f32) -> Ray {
+        Ray::new(vector(x, y, -1.), vector(0., 0., 1.))
+    }
+
+    #[test]
+    fn test_triangle_intersection() {
+        let triangle = Triangle {
+            vertices: [vector(0., 0., 0.), vector(1., 0., 0.), vector(0., 1., 0.)],
+            normals: None,
+            uvs: None,
+            material: material(),
+        };
+        let intersection = triangle.get_intersection(&ray_towards(0.25, 0.25)).unwrap();
+        assert_eq!(intersection.d, 1.);
+        assert_eq!(intersection.normal, vector(0., 0., 1.));
+        assert!(triangle
+            .get_intersection(&ray_towards(0.75, 0.75))
+            .is_none());
+    }
+
+    #[test]
+    fn test_smooth_normals() {
+        let triangle = Triangle {
+            vertices: [vector(0., 0., 0.), vector(1., 0., 0.), vector(0., 1., 0.)],
+            normals: Some([vector(0., 0., 1.), vector(1., 0., 0.), vector(0., 1., 0.)]),
+            uvs: None,
+            material: material(),
+        };
+        let intersection = triangle.get_intersection(&ray_towards(0.5, 0.)).unwrap();
+        assert_eq!(intersection.normal, vector(1., 0., 1.).normalize());
+    }
+
+    #[test]
+    fn test_uv_derivatives() {
+        let vertices = [vector(0., 0., 0.), vector(2., 0., 0.), vector(0., 4., 0.)];
+        // u grows along x and v along y, over the whole triangle
+        let (dpdu, dpdv) = uv_derivatives(vertices, Some([[0., 0.], [1., 0.], [0., 1.]]));
+        assert_eq!((dpdu, dpdv), (vector(2., 0., 0.), vector(0., 4., 0.)));
+        // the texture is turned by a quarter
+        let (dpdu, dpdv) = uv_derivatives(vertices, Some([[0., 0.], [0., 1.], [-1., 0.]]));
+        assert_eq!((dpdu, dpdv), (vector(0., -4., 0.), vector(2., 0., 0.)));
+        // the texture coordinates do not span the triangle
+        let (dpdu, dpdv) = uv_derivatives(vertices, Some([[0., 0.]; 3]));
+        assert_eq!(dpdu.dot(&vector(0., 0., 1.)), 0.);
+        assert_eq!(dpdu.cross(&dpdv), vector(0., 0., 1.));
+    }
+
+    #[test]
+    fn test_mesh() {
+        let positions = vec![
+            vector(0., 0., 0.),
+            vector(1., 0., 0.),
+            vector(1., 1., 0.),
+            vector(0., 1., 0.),
+        ];
+        let uvs = vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.]];
+        let mesh = TriangleMesh::new(
+            positions,
+            None,
+            Some(uvs),
+            vec![[0, 1, 2], [0, 2, 3]],
+            material(),
+        )
+        .unwrap();
+        assert_eq!(mesh.triangles_count(), 2);
+        assert_eq!(mesh.get_uv(1, 0.5, 0.5), [0.5, 1.]);
+        assert!(mesh.get_intersection(&ray_towards(0.25, 0.75)).is_some());
+        assert!(mesh.get_intersection(&ray_towards(0.75, 0.25)).is_some());
+        assert!(mesh.get_intersection(&ray_towards(1.5, 0.5)).is_none());
+    }
+
+    #[test]
+    fn test_invalid_mesh() {
+        let positions = vec![vector(0., 0., 0.), vector(1., 0., 0.), vector(1., 1., 0.)];
+        assert_eq!(
+            TriangleMesh::new(positions.clone(), None, None, vec![[0, 1, 3]], material()).err(),
+            Some(MeshError::IndexOutOfBounds {
+                triangle: 0,
+                index: 3
+            })
+        );
+        assert!(TriangleMesh::new(positions, Some(vec![]), None, vec![], material()).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! The textures, which vary the parameters of the materials over the surfaces of the shapes.
+//!
+//! A texture maps the texture coordinates `[u, v]` of a point of a surface, computed by its
+//! shape, to a linear color. Reflectances are given as fractions of each channel, and scalar
+//! parameters such as the roughness are read from the average of the channels.
+use crate::utils::color::Color;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+mod bitmap;
+mod noise;
+
+pub use bitmap::{Bitmap, ColorSpace, WrapMode};
+pub use noise::Noise;
+
+pub trait Texture: Send + Sync + Debug {
+    /// Return the value of the texture at the given texture coordinates
+    fn value(&self, uv: [f32; 2]) -> Color;
+
+    /// Return the value of a texture holding a scalar parameter
+    fn scalar(&self, uv: [f32; 2]) -> f32 {
+        self.value(uv).mean()
+    }
+}
+
+/// A uniform texture
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Constant(pub Color);
+
+impl Texture for Constant {
+    fn value(&self, _uv: [f32; 2]) -> Color {
+        self.0
+    }
+}
+
+/// A checkerboard alternating between two textures
+#[derive(Debug, Clone)]
+pub struct Checker {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    /// the number of squares per unit of the texture coordinates
+    pub scale: f32,
+}
+
+impl Texture for Checker {
+    fn value(&self, uv: [f32; 2]) -> Color {
+        let [u, v] = uv.map(|coordinate| (coordinate * self.scale).floor() as i64);
+        if (u + v).rem_euclid(2) == 0 {
+            self.even.value(uv)
+        } else {
+            self.odd.value(uv)
+        }
+    }
+}
+
+/// A linear transition between two textures
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub start: Arc<dyn Texture>,
+    pub end: Arc<dyn Texture>,
+    /// the direction of the transition in the texture coordinates, whose length is the
+    /// inverse of the length of the transition
+    pub direction: [f32; 2],
+}
+
+impl Texture for Gradient {
+    fn value(&self, uv: [f32; 2]) -> Color {
+        let t = (uv[0] * self.direction[0] + uv[1] * self.direction[1]).clamp(0., 1.);
+        mix(self.start.value(uv), self.end.value(uv), t)
+    }
+}
+
+/// Return the linear interpolation between two values
+fn mix(start: Color, end: Color, t: f32) -> Color {
+    start * (1. - t) + end * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant(value: f32) -> Arc<dyn Texture> {
+        Arc::new(Constant(Color::gray(value)))
+    }
+
+    #[test]
+    fn test_checker() {
+        let checker = Checker {
+            even: constant(0.),
+            odd: constant(1.),
+            scale: 2.,
+        };
+        assert_eq!(checker.value([0.1, 0.1]), Color::gray(0.));
+        assert_eq!(checker.value([0.6, 0.1]), Color::gray(1.));
+        assert_eq!(checker.value([0.6, 0.6]), Color::gray(0.));
+        // the squares go on along negative coordinates
+        assert_eq!(checker.value([-0.1, 0.1]), Color::gray(1.));
+    }
+
+    #[test]
+    fn test_gradient() {
+        let gradient = Gradient {
+            start: constant(0.),
+            end: constant(1.),
+            direction: [0., 0.5],
+        };
+        assert_eq!(gradient.value([0.3, 1.]), Color::gray(0.5));
+        assert_eq!(gradient.value([0.3, 3.]), Color::gray(1.));
+        assert_eq!(gradient.scalar([0.3, -1.]), 0.);
+    }
+}
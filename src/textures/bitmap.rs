@@ -0,0 +1,169 @@
+use crate::textures::Texture;
+use crate::utils::color::Color;
+use image::{DynamicImage, Rgb32FImage};
+use serde::Deserialize;
+use std::path::Path;
+
+/// How the texture coordinates outside of `[0, 1]` are mapped onto an image
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+    /// the image is tiled
+    #[default]
+    Repeat,
+    /// the pixels of the border are stretched
+    Clamp,
+    /// the image is tiled, every other tile being flipped so that the tiles join seamlessly
+    Mirror,
+}
+
+impl WrapMode {
+    /// Return the index of the pixel of a row or column of `size` pixels used for `index`
+    fn wrap(&self, index: i64, size: u32) -> u32 {
+        let size = size as i64;
+        let index = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Clamp => index.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                if index < size {
+                    index
+                } else {
+                    2 * size - 1 - index
+                }
+            }
+        };
+        index as u32
+    }
+}
+
+/// How the values of the pixels of an image are decoded into linear colors
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorSpace {
+    /// sRGB for the 8 and 16-bit images, linear for the floating point ones
+    #[default]
+    Auto,
+    /// the sRGB transfer function of the displays, used by most pictures
+    Srgb,
+    /// the values are stored as they are, as in normal and height maps
+    Linear,
+}
+
+/// An image mapped onto the texture coordinates, the bottom left corner at `[0, 0]` and the
+/// top right one at `[1, 1]`, and bilinearly filtered
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    pub image: Rgb32FImage,
+    pub wrap: WrapMode,
+}
+
+impl Bitmap {
+    /// Load an image, in any format supported by the `image` crate, into linear colors
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        wrap: WrapMode,
+        color_space: ColorSpace,
+    ) -> image::ImageResult<Self> {
+        let image = image::open(path)?;
+        let srgb = match color_space {
+            ColorSpace::Auto => !matches!(
+                image,
+                DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+            ),
+            ColorSpace::Srgb => true,
+            ColorSpace::Linear => false,
+        };
+        let mut image = image.to_rgb32f();
+        if srgb {
+            for pixel in image.pixels_mut() {
+                pixel.0 = Color::from(pixel.0).decode_srgb().to_array();
+            }
+        }
+        Ok(Self { image, wrap })
+    }
+
+    fn pixel(&self, x: i64, y: i64) -> Color {
+        let (width, height) = self.image.dimensions();
+        Color::from(
+            self.image
+                .get_pixel(self.wrap.wrap(x, width), self.wrap.wrap(y, height))
+                .0,
+        )
+    }
+}
+
+impl Texture for Bitmap {
+    fn value(&self, uv: [f32; 2]) -> Color {
+        let (width, height) = self.image.dimensions();
+        // the centers of the pixels are at half-integer coordinates
+        let x = uv[0] * width as f32 - 0.5;
+        let y = (1. - uv[1]) * height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let mut color = Color::BLACK;
+        for (dx, dy, weight) in [
+            (0, 0, (1. - tx) * (1. - ty)),
+            (1, 0, tx * (1. - ty)),
+            (0, 1, (1. - tx) * ty),
+            (1, 1, tx * ty),
+        ] {
+            color += self.pixel(x0 + dx, y0 + dy) * weight;
+        }
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    /// A 2×1 image, black on the left and white on the right
+    fn bitmap(wrap: WrapMode) -> Bitmap {
+        Bitmap {
+            image: Rgb32FImage::from_fn(2, 1, |x, _| Rgb([x as f32; 3])),
+            wrap,
+        }
+    }
+
+    #[test]
+    fn test_bilinear_filtering() {
+        let bitmap = bitmap(WrapMode::Clamp);
+        assert_eq!(bitmap.value([0.25, 0.5]), Color::gray(0.));
+        assert_eq!(bitmap.value([0.75, 0.5]), Color::gray(1.));
+        assert_eq!(bitmap.value([0.5, 0.5]), Color::gray(0.5));
+        assert_eq!(bitmap.value([0.1, 0.5]), Color::gray(0.));
+    }
+
+    #[test]
+    fn test_wrap_modes() {
+        // halfway between the last pixel and the first one of the next tile
+        assert_eq!(bitmap(WrapMode::Repeat).value([1., 0.5]), Color::gray(0.5));
+        assert_eq!(bitmap(WrapMode::Clamp).value([1., 0.5]), Color::gray(1.));
+        assert_eq!(bitmap(WrapMode::Mirror).value([1., 0.5]), Color::gray(1.));
+        assert_eq!(bitmap(WrapMode::Mirror).value([1.25, 0.5]), Color::gray(1.));
+        assert_eq!(bitmap(WrapMode::Mirror).value([1.75, 0.5]), Color::gray(0.));
+    }
+
+    #[test]
+    fn test_color_spaces() {
+        let path =
+            std::env::temp_dir().join(format!("ray-tracer-bitmap-{}.png", std::process::id()));
+        image::RgbImage::from_pixel(1, 1, Rgb([128, 0, 255]))
+            .save(&path)
+            .unwrap();
+        let value = |color_space| {
+            Bitmap::open(&path, WrapMode::Repeat, color_space)
+                .unwrap()
+                .value([0.5, 0.5])
+        };
+        let decoded = value(ColorSpace::Auto);
+        assert!((decoded.r - 0.2159).abs() < 1e-4, "{decoded:?}");
+        assert_eq!((decoded.g, decoded.b), (0., 1.));
+        assert_eq!(value(ColorSpace::Srgb), decoded);
+        assert_eq!(value(ColorSpace::Linear).r, 128. / 255.);
+        std::fs::remove_file(path).unwrap();
+    }
+}
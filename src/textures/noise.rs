@@ -0,0 +1,126 @@
+use crate::textures::{mix, Texture};
+use crate::utils::color::Color;
+use rand::prelude::*;
+use std::sync::Arc;
+
+/// The number of distinct gradients of the noise lattice, after which the noise repeats
+const LATTICE_SIZE: usize = 256;
+
+/// A blend of two textures following Perlin gradient noise, summed over several octaves
+/// (fractal Brownian motion) for marble, clouds or stains
+#[derive(Debug, Clone)]
+pub struct Noise {
+    pub low: Arc<dyn Texture>,
+    pub high: Arc<dyn Texture>,
+    /// the number of features per unit of the texture coordinates
+    pub scale: f32,
+    /// the number of summed layers of noise, each twice as fine and half as strong as the
+    /// previous one
+    pub octaves: u32,
+    /// a random permutation of the lattice indices, repeated twice to avoid wrapping them
+    permutation: Vec<usize>,
+}
+
+impl Noise {
+    /// Create a noise texture, whose pattern is chosen by the seed
+    pub fn new(
+        low: Arc<dyn Texture>,
+        high: Arc<dyn Texture>,
+        scale: f32,
+        octaves: u32,
+        seed: u64,
+    ) -> Self {
+        let mut permutation: Vec<usize> = (0..LATTICE_SIZE).collect();
+        permutation.shuffle(&mut StdRng::seed_from_u64(seed));
+        permutation.extend_from_within(..);
+        Self {
+            low,
+            high,
+            scale,
+            octaves: octaves.max(1),
+            permutation,
+        }
+    }
+
+    /// Return the noise at a point, between -1 and 1
+    fn noise(&self, x: f32, y: f32) -> f32 {
+        let (cell_x, cell_y) = (x.floor(), y.floor());
+        let (fx, fy) = (x - cell_x, y - cell_y);
+        let i = cell_x.rem_euclid(LATTICE_SIZE as f32) as usize;
+        let j = cell_y.rem_euclid(LATTICE_SIZE as f32) as usize;
+        let corner = |di: usize, dj: usize| {
+            let hash = self.permutation[self.permutation[i + di] + j + dj];
+            let angle = hash as f32 * std::f32::consts::TAU / LATTICE_SIZE as f32;
+            angle.cos() * (fx - di as f32) + angle.sin() * (fy - dj as f32)
+        };
+        let (sx, sy) = (fade(fx), fade(fy));
+        let bottom = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * sx;
+        let top = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * sx;
+        // the gradients are unit vectors, so the noise is at most √2 / 2
+        (bottom + (top - bottom) * sy) * std::f32::consts::SQRT_2
+    }
+
+    /// Return the sum of the octaves of the noise at a point, between 0 and 1
+    fn turbulence(&self, x: f32, y: f32) -> f32 {
+        let (mut total, mut amplitude, mut frequency, mut norm) = (0., 1., 1., 0.);
+        for _ in 0..self.octaves {
+            total += amplitude * self.noise(x * frequency, y * frequency);
+            norm += amplitude;
+            amplitude /= 2.;
+            frequency *= 2.;
+        }
+        (0.5 + total / norm / 2.).clamp(0., 1.)
+    }
+}
+
+/// The quintic smoothstep of Perlin's improved noise, whose first and second derivatives
+/// vanish at the lattice points
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+impl Texture for Noise {
+    fn value(&self, uv: [f32; 2]) -> Color {
+        let t = self.turbulence(uv[0] * self.scale, uv[1] * self.scale);
+        mix(self.low.value(uv), self.high.value(uv), t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::textures::Constant;
+
+    fn noise(octaves: u32, seed: u64) -> Noise {
+        Noise::new(
+            Arc::new(Constant(Color::BLACK)),
+            Arc::new(Constant(Color::WHITE)),
+            4.,
+            octaves,
+            seed,
+        )
+    }
+
+    #[test]
+    fn test_noise() {
+        let noise = noise(4, 0);
+        let values: Vec<f32> = (0..1000)
+            .map(|i| noise.scalar([i as f32 * 0.013, i as f32 * 0.007]))
+            .collect();
+        assert!(values.iter().all(|value| (0. ..=1.).contains(value)));
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        assert!((mean - 0.5).abs() < 0.1, "{mean}");
+        assert!(values.iter().any(|value| (value - 0.5).abs() > 0.1));
+
+        // the noise vanishes on the lattice, and is continuous
+        assert_eq!(noise.noise(3., -7.), 0.);
+        assert!((noise.noise(3.001, -7.) - noise.noise(3., -7.)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_noise_seed() {
+        let uv = [0.37, 0.81];
+        assert_eq!(noise(2, 1).value(uv), noise(2, 1).value(uv));
+        assert_ne!(noise(2, 1).value(uv), noise(2, 2).value(uv));
+    }
+}
@@ -1,3 +1,6 @@
+pub mod aabb;
+pub mod color;
 pub mod intersection;
+pub mod matrix;
 pub mod ray;
 pub mod vector;
@@ -0,0 +1,172 @@
+use crate::utils::ray::Ray;
+use crate::utils::vector::Vector;
+
+/// An axis-aligned bounding box
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl Aabb {
+    /// The box of unbounded shapes, such as planes
+    pub const INFINITE: Aabb = Aabb {
+        min: Vector {
+            x: f32::NEG_INFINITY,
+            y: f32::NEG_INFINITY,
+            z: f32::NEG_INFINITY,
+        },
+        max: Vector {
+            x: f32::INFINITY,
+            y: f32::INFINITY,
+            z: f32::INFINITY,
+        },
+    };
+
+    /// The empty box, neutral element of `union`
+    pub const EMPTY: Aabb = Aabb {
+        min: Vector {
+            x: f32::INFINITY,
+            y: f32::INFINITY,
+            z: f32::INFINITY,
+        },
+        max: Vector {
+            x: f32::NEG_INFINITY,
+            y: f32::NEG_INFINITY,
+            z: f32::NEG_INFINITY,
+        },
+    };
+
+    /// The smallest box containing all the given points
+    pub fn from_points<I: IntoIterator<Item = Vector>>(points: I) -> Self {
+        points.into_iter().fold(Aabb::EMPTY, |aabb, point| Aabb {
+            min: min(aabb.min, point),
+            max: max(aabb.max, point),
+        })
+    }
+
+    /// The smallest box containing both boxes
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: min(self.min, other.min),
+            max: max(self.max, other.max),
+        }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        [self.min, self.max]
+            .iter()
+            .all(|v| v.x.is_finite() && v.y.is_finite() && v.z.is_finite())
+    }
+
+    pub fn centroid(&self) -> Vector {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Return the distance at which the ray enters the box, if it does within its interval
+    pub fn intersect(&self, ray: &Ray) -> Option<f32> {
+        let mut t_near = ray.t_min;
+        let mut t_far = ray.t_max;
+        for (origin, direction, min, max) in [
+            (ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+            (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+            (ray.origin.z, ray.direction.z, self.min.z, self.max.z),
+        ] {
+            let inverse = 1. / direction;
+            let mut t0 = (min - origin) * inverse;
+            let mut t1 = (max - origin) * inverse;
+            if inverse < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // `max` and `min` ignore the NaN raised by rays parallel to a face of the box
+            t_near = t_near.max(t0);
+            t_far = t_far.min(t1);
+            if t_near > t_far {
+                return None;
+            }
+        }
+        Some(t_near)
+    }
+}
+
+fn min(a: Vector, b: Vector) -> Vector {
+    Vector {
+        x: a.x.min(b.x),
+        y: a.y.min(b.y),
+        z: a.z.min(b.z),
+    }
+}
+
+fn max(a: Vector, b: Vector) -> Vector {
+    Vector {
+        x: a.x.max(b.x),
+        y: a.y.max(b.y),
+        z: a.z.max(b.z),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIT: Aabb = Aabb {
+        min: Vector {
+            x: -1.,
+            y: -1.,
+            z: -1.,
+        },
+        max: Vector {
+            x: 1.,
+            y: 1.,
+            z: 1.,
+        },
+    };
+
+    #[test]
+    fn test_intersect() {
+        let ray = Ray::new(
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: -5.,
+            },
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: 1.,
+            },
+        );
+        assert_eq!(UNIT.intersect(&ray), Some(4.));
+        let short_ray = Ray { t_max: 3., ..ray };
+        assert_eq!(UNIT.intersect(&short_ray), None);
+    }
+
+    #[test]
+    fn test_miss() {
+        let ray = Ray::new(
+            Vector {
+                x: 0.,
+                y: 2.,
+                z: -5.,
+            },
+            Vector {
+                x: 0.,
+                y: 0.,
+                z: 1.,
+            },
+        );
+        assert_eq!(UNIT.intersect(&ray), None);
+    }
+
+    #[test]
+    fn test_union() {
+        let other = Aabb::from_points([Vector {
+            x: 2.,
+            y: 0.,
+            z: 0.,
+        }]);
+        assert_eq!(UNIT.union(&other).max.x, 2.);
+        assert_eq!(UNIT.union(&Aabb::EMPTY), UNIT);
+        assert!(!UNIT.union(&Aabb::INFINITE).is_finite());
+    }
+}
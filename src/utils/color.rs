@@ -0,0 +1,185 @@
+use serde::Deserialize;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign};
+
+/// A color in linear RGB, whose channels are proportional to the amount of light and are
+/// not bounded. A white surface receiving a unit of light is displayed white, without
+/// exposure. Deserialized from a `[r, g, b]` array.
+#[derive(Debug, Copy, Clone, PartialEq, Default, Deserialize)]
+#[serde(from = "[f32; 3]")]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::gray(0.);
+    pub const WHITE: Color = Color::gray(1.);
+
+    /// Return the color whose channels all have the same value
+    pub const fn gray(value: f32) -> Self {
+        Self {
+            r: value,
+            g: value,
+            b: value,
+        }
+    }
+
+    /// Apply a function to every channel
+    pub fn map<F: Fn(f32) -> f32>(self, f: F) -> Self {
+        Self {
+            r: f(self.r),
+            g: f(self.g),
+            b: f(self.b),
+        }
+    }
+
+    pub fn to_array(self) -> [f32; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    /// The average of the channels
+    pub fn mean(&self) -> f32 {
+        (self.r + self.g + self.b) / 3.
+    }
+
+    pub fn max_channel(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    pub fn is_black(&self) -> bool {
+        self.r <= 0. && self.g <= 0. && self.b <= 0.
+    }
+
+    /// Encode the channels, from 0 to 1, with the sRGB transfer function of the displays
+    pub fn encode_srgb(self) -> Self {
+        self.map(|value| {
+            if value <= 0.003_130_8 {
+                12.92 * value
+            } else {
+                1.055 * value.powf(1. / 2.4) - 0.055
+            }
+        })
+    }
+
+    /// Decode the channels of an sRGB-encoded color, from 0 to 1, into linear values
+    pub fn decode_srgb(self) -> Self {
+        self.map(|value| {
+            if value <= 0.040_45 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    }
+}
+
+impl From<[f32; 3]> for Color {
+    fn from(c: [f32; 3]) -> Self {
+        Self {
+            r: c[0],
+            g: c[1],
+            b: c[2],
+        }
+    }
+}
+
+impl Add for Color {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
+        }
+    }
+}
+
+impl AddAssign for Color {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sum for Color {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Color::BLACK, Add::add)
+    }
+}
+
+/// The light of each channel filtered by a color
+impl Mul for Color {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            r: self.r * other.r,
+            g: self.g * other.g,
+            b: self.b * other.b,
+        }
+    }
+}
+
+impl MulAssign for Color {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl Mul<f32> for Color {
+    type Output = Self;
+
+    fn mul(self, other: f32) -> Self {
+        self.map(|value| value * other)
+    }
+}
+
+impl MulAssign<f32> for Color {
+    fn mul_assign(&mut self, other: f32) {
+        *self = *self * other;
+    }
+}
+
+impl Div<f32> for Color {
+    type Output = Self;
+
+    fn div(self, other: f32) -> Self {
+        self.map(|value| value / other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLOR: Color = Color {
+        r: 0.5,
+        g: 1.,
+        b: 2.,
+    };
+
+    #[test]
+    fn test_operations() {
+        assert_eq!(COLOR + Color::WHITE, Color::from([1.5, 2., 3.]));
+        assert_eq!(COLOR * COLOR, Color::from([0.25, 1., 4.]));
+        assert_eq!(COLOR * 2., Color::from([1., 2., 4.]));
+        assert_eq!(COLOR / 2., Color::from([0.25, 0.5, 1.]));
+        assert_eq!([COLOR, COLOR].into_iter().sum::<Color>(), COLOR * 2.);
+        assert_eq!(COLOR.max_channel(), 2.);
+        assert!(Color::BLACK.is_black() && !COLOR.is_black());
+    }
+
+    #[test]
+    fn test_srgb() {
+        assert_eq!(Color::BLACK.encode_srgb(), Color::BLACK);
+        assert!((Color::WHITE.encode_srgb().r - 1.).abs() < 1e-6);
+        // a mid-gray surface reflecting 18% of the light is displayed near the middle
+        assert!((Color::gray(0.18).encode_srgb().r - 0.461).abs() < 1e-3);
+        for value in [0.001, 0.2, 0.7] {
+            let decoded = Color::gray(value).encode_srgb().decode_srgb();
+            assert!((decoded.r - value).abs() < 1e-6);
+        }
+    }
+}
@@ -8,7 +8,13 @@ pub struct Intersection<'a> {
     pub intersection: Vector,
     /// the normal to the point of intersection
     pub normal: Vector,
-    pub shape: &'a (dyn Shape),
+    /// the texture coordinates of the point of intersection
+    pub uv: [f32; 2],
+    /// the derivatives of the point along the texture coordinates u and v, tangent to the
+    /// surface, which orient the normal maps
+    pub dpdu: Vector,
+    pub dpdv: Vector,
+    pub shape: &'a dyn Shape,
 }
 
 // impl Intersection {
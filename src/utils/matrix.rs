@@ -0,0 +1,246 @@
+use crate::utils::vector::Vector;
+use std::ops::Mul;
+
+/// A 4x4 matrix, acting on homogeneous coordinates
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Matrix {
+    /// the coefficients, row by row
+    pub m: [[f32; 4]; 4],
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix {
+        m: [
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ],
+    };
+
+    pub fn translation(v: Vector) -> Self {
+        Self {
+            m: [
+                [1., 0., 0., v.x],
+                [0., 1., 0., v.y],
+                [0., 0., 1., v.z],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    pub fn scaling(v: Vector) -> Self {
+        Self {
+            m: [
+                [v.x, 0., 0., 0.],
+                [0., v.y, 0., 0.],
+                [0., 0., v.z, 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    /// The rotation around the x axis, of an angle in radians
+    pub fn rotation_x(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            m: [
+                [1., 0., 0., 0.],
+                [0., cos, -sin, 0.],
+                [0., sin, cos, 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    /// The rotation around the y axis, of an angle in radians
+    pub fn rotation_y(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            m: [
+                [cos, 0., sin, 0.],
+                [0., 1., 0., 0.],
+                [-sin, 0., cos, 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    /// The rotation around the z axis, of an angle in radians
+    pub fn rotation_z(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            m: [
+                [cos, -sin, 0., 0.],
+                [sin, cos, 0., 0.],
+                [0., 0., 1., 0.],
+                [0., 0., 0., 1.],
+            ],
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Self { m }
+    }
+
+    /// Return the inverse of the matrix, computed by Gauss-Jordan elimination, or `None` if
+    /// the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inverse = Matrix::IDENTITY.m;
+        for column in 0..4 {
+            let pivot =
+                (column..4).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))?;
+            if a[pivot][column].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(column, pivot);
+            inverse.swap(column, pivot);
+            let factor = 1. / a[column][column];
+            for j in 0..4 {
+                a[column][j] *= factor;
+                inverse[column][j] *= factor;
+            }
+            for row in 0..4 {
+                if row != column {
+                    let factor = a[row][column];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[column][j];
+                        inverse[row][j] -= factor * inverse[column][j];
+                    }
+                }
+            }
+        }
+        Some(Self { m: inverse })
+    }
+
+    /// Transform a point, which is affected by translations
+    pub fn transform_point(&self, v: &Vector) -> Vector {
+        let m = &self.m;
+        Vector {
+            x: m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z + m[0][3],
+            y: m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z + m[1][3],
+            z: m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z + m[2][3],
+        }
+    }
+
+    /// Transform a direction, which is not affected by translations
+    pub fn transform_vector(&self, v: &Vector) -> Vector {
+        let m = &self.m;
+        Vector {
+            x: m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            y: m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            z: m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        }
+    }
+}
+
+impl Mul for Matrix {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Self { m }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const VECTOR: Vector = Vector {
+        x: 1.0,
+        y: 2.0,
+        z: 3.0,
+    };
+
+    fn assert_close(a: Vector, b: Vector) {
+        assert!((a - b).square_norm() < 1e-10, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_translation() {
+        let translation = Matrix::translation(VECTOR);
+        assert_eq!(
+            translation.transform_point(&VECTOR),
+            Vector {
+                x: 2.0,
+                y: 4.0,
+                z: 6.0
+            }
+        );
+        assert_eq!(translation.transform_vector(&VECTOR), VECTOR);
+    }
+
+    #[test]
+    fn test_rotation() {
+        assert_close(
+            Matrix::rotation_z(PI / 2.).transform_vector(&VECTOR),
+            Vector {
+                x: -2.0,
+                y: 1.0,
+                z: 3.0,
+            },
+        );
+        assert_close(
+            Matrix::rotation_x(PI / 2.).transform_vector(&VECTOR),
+            Vector {
+                x: 1.0,
+                y: -3.0,
+                z: 2.0,
+            },
+        );
+        assert_close(
+            Matrix::rotation_y(PI / 2.).transform_vector(&VECTOR),
+            Vector {
+                x: 3.0,
+                y: 2.0,
+                z: -1.0,
+            },
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        let matrix = Matrix::translation(VECTOR)
+            * Matrix::rotation_y(0.3)
+            * Matrix::scaling(Vector {
+                x: 2.0,
+                y: 0.5,
+                z: 1.0,
+            });
+        let inverse = matrix.inverse().unwrap();
+        assert_close(
+            inverse.transform_point(&matrix.transform_point(&VECTOR)),
+            VECTOR,
+        );
+        assert_eq!(
+            Matrix::scaling(Vector {
+                x: 0.,
+                y: 1.,
+                z: 1.
+            })
+            .inverse(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_transpose() {
+        let matrix = Matrix::translation(VECTOR);
+        assert_eq!(matrix.transpose().m[3], [1.0, 2.0, 3.0, 1.0]);
+        assert_eq!(matrix.transpose().transpose(), matrix);
+    }
+}
@@ -1,52 +1,173 @@
-use crate::shapes::Shape;
 use crate::utils::intersection::Intersection;
 use crate::utils::vector::Vector;
 
-/// A ray
+/// The default minimal distance of an intersection, which prevents a ray leaving a surface
+/// from intersecting it again because of floating-point errors
+pub const T_MIN: f32 = 1e-3;
+
+/// A ray, along which only the intersections between `t_min` and `t_max` are considered
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Ray {
     pub origin: Vector,
+    /// the direction of the ray, which must be normalized
     pub direction: Vector,
+    pub t_min: f32,
+    pub t_max: f32,
 }
 
 impl Ray {
+    /// Create a ray considering the intersections from `T_MIN` to infinity
+    pub fn new(origin: Vector, direction: Vector) -> Self {
+        Self {
+            origin,
+            direction,
+            t_min: T_MIN,
+            t_max: f32::INFINITY,
+        }
+    }
+
+    /// Return whether an intersection at distance `d` is in the interval of the ray
+    pub fn contains(&self, d: f32) -> bool {
+        self.t_min < d && d < self.t_max
+    }
+
+    /// Start the ray again from the intersection, in the given direction
+    fn restart(&mut self, intersection: &Intersection, direction: Vector) {
+        *self = Ray::new(intersection.intersection, direction);
+    }
+
     pub fn reflect(&mut self, intersection: &Intersection) {
-        self.direction = (self.direction
+        let direction = (self.direction
             - intersection.normal * 2. * intersection.normal.dot(&self.direction))
         .normalize();
-        self.origin = intersection.intersection;
+        self.restart(intersection, direction);
     }
 
+    /// Refract the ray through the surface of a transparent material, following the
+    /// Snell–Descartes law, or reflect it in case of total internal reflection
     pub fn refract(&mut self, intersection: &Intersection) {
-        let is_ray_entering_the_shape = intersection.normal.dot(&self.direction) < 0.;
-        if is_ray_entering_the_shape {
-            let coeff = 1.
-                - (1. - self.direction.dot(&intersection.normal).powi(2))
-                    / intersection.shape.get_material().refractive_index.powi(2);
-            self.direction = (self.direction
-                * (1. / intersection.shape.get_material().refractive_index)
-                - intersection.normal
-                    * (self.direction.dot(&intersection.normal)
-                        / intersection.shape.get_material().refractive_index
-                        + coeff.sqrt()))
-            .normalize();
-            self.origin = intersection.intersection - intersection.normal * 0.05;
-            // prevents a bug
+        let (eta, normal, cos_i) = self.interface(intersection);
+        let sin2_t = eta.powi(2) * (1. - cos_i.powi(2));
+        if sin2_t > 1. {
+            return self.reflect(intersection);
+        }
+        let direction =
+            (self.direction * eta + normal * (eta * cos_i - (1. - sin2_t).sqrt())).normalize();
+        self.restart(intersection, direction);
+    }
+
+    /// Return the fraction of the light reflected by the surface of a transparent material,
+    /// the rest being refracted, following Schlick's approximation of the Fresnel equations
+    pub fn reflectance(&self, intersection: &Intersection) -> f32 {
+        let (eta, _, cos_i) = self.interface(intersection);
+        let sin2_t = eta.powi(2) * (1. - cos_i.powi(2));
+        if sin2_t > 1. {
+            return 1.;
+        }
+        // the approximation holds for the angle on the side of the lower index
+        let cos = if eta > 1. {
+            (1. - sin2_t).sqrt()
+        } else {
+            cos_i
+        };
+        let r0 = ((eta - 1.) / (eta + 1.)).powi(2);
+        r0 + (1. - r0) * (1. - cos).powi(5)
+    }
+
+    /// Return whether the ray hits the surface of a shape from the inside
+    pub fn is_leaving(&self, intersection: &Intersection) -> bool {
+        intersection.normal.dot(&self.direction) > 0.
+    }
+
+    /// Return the ratio of the refractive index the ray leaves over the one it enters, the
+    /// normal facing the ray, and the cosine of the angle of incidence
+    fn interface(&self, intersection: &Intersection) -> (f32, Vector, f32) {
+        let material = intersection.shape.get_material();
+        let (eta, normal) = if self.is_leaving(intersection) {
+            (
+                material.refractive_index / material.outer_refractive_index,
+                intersection.normal * -1.,
+            )
         } else {
-            // intersection.normal *= -1
-            let inverted_normal = intersection.normal * -1.;
-            let coeff = 1.
-                - (1. - self.direction.dot(&inverted_normal).powi(2))
-                    / intersection.shape.get_material().refractive_index.powi(2);
-            if coeff < 0. {
-                return self.reflect(intersection);
-            }
-            self.direction = (self.direction * intersection.shape.get_material().refractive_index
-                - inverted_normal
-                    * (self.direction.dot(&inverted_normal)
-                        * intersection.shape.get_material().refractive_index
-                        + coeff.sqrt()))
-            .normalize();
-            self.origin = intersection.intersection + intersection.normal * 0.05;
+            (
+                material.outer_refractive_index / material.refractive_index,
+                intersection.normal,
+            )
+        };
+        (eta, normal, -self.direction.dot(&normal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Material;
+    use crate::shapes::{Shape, Sphere};
+    use crate::utils::color::Color;
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector {
+        Vector { x, y, z }
+    }
+
+    /// A glass ball of radius 1 centered on the origin
+    fn ball(outer_refractive_index: f32) -> Sphere {
+        Sphere {
+            origin: vector(0., 0., 0.),
+            radius: 1.,
+            material: Material {
+                refractive_index: 1.5,
+                outer_refractive_index,
+                ..Material::diffuse(Color::WHITE)
+            },
         }
     }
+
+    /// Return the sine of the angle between a direction and a normal
+    fn sine(direction: Vector, normal: Vector) -> f32 {
+        direction.cross(&normal).square_norm().sqrt()
+    }
+
+    #[test]
+    fn test_reflectance() {
+        let ball = ball(1.);
+        let ray = Ray::new(vector(0., 2., 0.), vector(0., -1., 0.));
+        let intersection = ball.get_intersection(&ray).unwrap();
+        assert!((ray.reflectance(&intersection) - 0.04).abs() < 1e-4);
+
+        // grazing rays are mostly reflected
+        let ray = Ray::new(vector(-2., 0.999, 0.), vector(1., 0., 0.));
+        let intersection = ball.get_intersection(&ray).unwrap();
+        assert!(ray.reflectance(&intersection) > 0.5);
+
+        // beyond the critical angle, the light cannot leave the ball
+        let ray = Ray::new(vector(0.8, 0., 0.), vector(0., 1., 0.));
+        let intersection = ball.get_intersection(&ray).unwrap();
+        assert!(ray.is_leaving(&intersection));
+        assert_eq!(ray.reflectance(&intersection), 1.);
+    }
+
+    #[test]
+    fn test_refract() {
+        let (glass, glass_under_water) = (ball(1.), ball(1.33));
+        let ray = Ray::new(vector(-0.6, 1.8, 0.), vector(0.6, -0.8, 0.));
+        let intersection = glass.get_intersection(&ray).unwrap();
+        let normal = intersection.normal;
+        assert!((normal.y - 1.).abs() < 1e-4);
+        let mut refracted = ray;
+        refracted.refract(&intersection);
+        assert!((sine(refracted.direction, normal) - 0.6 / 1.5).abs() < 1e-4);
+
+        // under water, the ray bends less
+        let mut refracted = ray;
+        refracted.refract(&glass_under_water.get_intersection(&ray).unwrap());
+        assert!((sine(refracted.direction, normal) - 1.33 * 0.6 / 1.5).abs() < 1e-4);
+
+        // leaving the ball, the ray bends away from the normal
+        let ray = Ray::new(vector(0.4, 0., 0.), vector(0., 1., 0.));
+        let intersection = glass.get_intersection(&ray).unwrap();
+        let mut refracted = ray;
+        refracted.refract(&intersection);
+        assert!((sine(refracted.direction, intersection.normal) - 0.6).abs() < 1e-4);
+        assert!(refracted.direction.dot(&intersection.normal) > 0.);
+    }
 }
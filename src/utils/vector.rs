@@ -1,6 +1,9 @@
+use serde::Deserialize;
 use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// A 3D vector. Deserialized from a `[x, y, z]` array.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(from = "[f32; 3]")]
 pub struct Vector {
     pub x: f32,
     pub y: f32,
@@ -24,6 +27,26 @@ impl Vector {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
 
+    /// Return two unit vectors forming an orthonormal basis with this one, which must be
+    /// normalized
+    pub fn tangents(&self) -> (Self, Self) {
+        let axis = if self.x.abs() < 0.9 {
+            Vector {
+                x: 1.,
+                y: 0.,
+                z: 0.,
+            }
+        } else {
+            Vector {
+                x: 0.,
+                y: 1.,
+                z: 0.,
+            }
+        };
+        let tangent_1 = self.cross(&axis).normalize();
+        (tangent_1, self.cross(&tangent_1))
+    }
+
     pub fn cross(&self, other: &Self) -> Self {
         Self {
             x: self.y * other.z - self.z * other.y,
@@ -33,6 +56,16 @@ impl Vector {
     }
 }
 
+impl From<[f32; 3]> for Vector {
+    fn from(v: [f32; 3]) -> Self {
+        Self {
+            x: v[0],
+            y: v[1],
+            z: v[2],
+        }
+    }
+}
+
 impl Add for Vector {
     type Output = Self;
 
@@ -121,7 +154,7 @@ mod tests {
 
     #[test]
     fn test_add_assign() {
-        let mut v = Vector::from(VECTOR);
+        let mut v = VECTOR;
         v += OTHER_VECTOR;
         assert_eq!(
             v,
@@ -147,7 +180,7 @@ mod tests {
 
     #[test]
     fn test_sub_assign() {
-        let mut v = Vector::from(VECTOR);
+        let mut v = VECTOR;
         v -= OTHER_VECTOR;
         assert_eq!(
             v,
@@ -173,7 +206,7 @@ mod tests {
 
     #[test]
     fn test_mul_assign() {
-        let mut v = Vector::from(VECTOR);
+        let mut v = VECTOR;
         v *= 2.0;
         assert_eq!(
             v,